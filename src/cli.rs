@@ -0,0 +1,61 @@
+﻿use std::path::PathBuf;
+use clap::Parser;
+use crate::logging::journal::DEFAULT_JOURNAL_MAX_BYTES;
+use crate::simulation::ClockMode;
+use crate::supervisor::perception::PerceptionConfig;
+
+/// command line arguments of the multiverse
+#[derive(Parser, Debug)]
+#[command(version, about = "tokio based multiverse simulation")]
+pub struct Args {
+    /// run without the terminal UI, reading commands from stdin
+    #[arg(long)]
+    pub headless: bool,
+
+    /// read commands from this file instead of stdin, implies --headless
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+
+    /// seed of every random roll, reuse one to replay the same battle
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// simulation speed: `real`, a factor like `10x`, or `step` to only advance with the step command
+    #[arg(long, value_name = "MODE", default_value = "real")]
+    pub clock: ClockMode,
+
+    /// write every event to this file as JSON lines, replacing an earlier run
+    #[arg(long, value_name = "FILE")]
+    pub journal: Option<PathBuf>,
+
+    /// rotate the journal once it grows past this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_JOURNAL_MAX_BYTES)]
+    pub journal_max_bytes: u64,
+
+    /// universes see each other's hp and status every this many ticks
+    #[arg(long, value_name = "TICKS", default_value_t = PerceptionConfig::default().interval,
+        value_parser = clap::value_parser!(u64).range(1..))]
+    pub view_every: u64,
+
+    /// fog of war: what universes see is this many ticks old
+    #[arg(long, value_name = "TICKS", default_value_t = PerceptionConfig::default().fog)]
+    pub fog: u64,
+
+    /// write the scoreboard here whenever a match ends, as json for `.json` files, a text table otherwise
+    #[arg(long, value_name = "FILE")]
+    pub scoreboard: Option<PathBuf>,
+
+    /// play a recorded journal back instead of running a simulation
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn is_headless(&self) -> bool {
+        self.headless || self.script.is_some()
+    }
+
+    pub fn perception(&self) -> PerceptionConfig {
+        PerceptionConfig { interval: self.view_every, fog: self.fog }
+    }
+}
@@ -0,0 +1,461 @@
+﻿use crate::supervisor::log_messages::*;
+use std::path::PathBuf;
+use crate::logging::SimulationEvent;
+use crate::replay::ReplayControl;
+use crate::simulation::{parse_speed_factor, ClockMode};
+use crate::supervisor::combat_stats::StatsColumn;
+use crate::supervisor::scoreboard::write_scoreboard;
+use crate::supervisor::supervisor_client::SupervisorClient;
+use crate::universe::{DiplomaticAction, UniverseCommand, UniverseEvent, UniverseOptions};
+
+/// the menus shared by every front-end, they all speak the same commands
+#[derive(Clone)]
+pub enum UiMode {
+    Main,
+    Manage { name: String },
+    EventMenu { name: String },
+    Replay(ReplayControl), // watching a recorded journal, nothing to manage
+}
+
+/// what the front-end has to do after a command, beyond what the command did itself
+pub enum CommandOutcome {
+    Continue,
+    Inspect(String), // show the state of this universe
+    CloseInspector,
+    Scoreboard, // show how the match goes
+    Leaderboard(StatsColumn), // show the run's combat stats sorted by this column
+    ToggleGraph, // relationship graph instead of the logs, and back
+    FilterLogs(LogFilterChange),
+    Shutdown,
+}
+
+/// narrows (or widens again) what the log panel shows
+#[derive(Debug, Clone)]
+pub enum LogFilterChange {
+    Kind(String),     // only events of this kind
+    Universe(String), // only events about this universe
+    Off,
+}
+
+/// parses user command lines and talks to the supervisor.
+/// front-ends only handle input/output and their own panels.
+pub struct CommandInterpreter {
+    client: SupervisorClient,
+    mode: UiMode,
+}
+
+impl CommandInterpreter {
+    pub fn new(client: SupervisorClient) -> Self {
+        CommandInterpreter {
+            client,
+            mode: UiMode::Main,
+        }
+    }
+
+    /// only the playback controls, there is no live simulation behind a replay
+    pub fn replaying(client: SupervisorClient, replay: ReplayControl) -> Self {
+        CommandInterpreter {
+            client,
+            mode: UiMode::Replay(replay),
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, UiMode::Replay(_))
+    }
+
+    pub fn mode_text(&self) -> String {
+        match &self.mode {
+            UiMode::Main => "new <name> [--targeting <strategy>] [--script <file>] [--faction <name>]\nlist\nscoreboard [file]\nleaderboard [name|dealt|taken|heals|kills|uptime]\ngraph\nfilter kind <kind>|universe <name>|off\nfactions\nfaction <name> pause|resume|heal\nqueues\nclock [real|step|<n>x]\nstep [ticks]\nsave <file>\nload <file>\nmanage <name>\nshutdown".into(),
+            UiMode::Manage { name } => format!(
+                "Managing '{}':\nresume\npause\nevent\nstate\ndeclare-war <other>\nally <other>\npeace <other>\ncollapse\nback",
+                name
+            ),
+            UiMode::EventMenu { name } => format!(
+                "Event on '{}':\nshatter\ncrash\nheal\nping <other>\npong <other>\nback",
+                name
+            ),
+            UiMode::Replay(_) => "Replay:\npause\nresume\nspeed <n>x\nseek <tick>\nstatus\nleaderboard [name|dealt|taken|heals|kills|uptime]\ngraph\nfilter kind <kind>|universe <name>|off\nshutdown".into(),
+        }
+    }
+
+    /// the commands of the current menu, for completing what the user types
+    pub fn command_names(&self) -> Vec<String> {
+        self.mode_text()
+            .lines()
+            .filter(|line| !line.ends_with(':')) // the menu title
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_owned)
+            .collect()
+    }
+
+    pub async fn execute(&mut self, line: &str) -> CommandOutcome {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            return CommandOutcome::Continue;
+        }
+
+        let mode = &self.mode;
+
+        match mode {
+            UiMode::Main => self.handle_main(parts).await,
+            UiMode::Manage { name } => self.handle_manage(name.clone(), parts).await,
+            UiMode::EventMenu { name } => self.handle_user_event(name.clone(), parts).await,
+            UiMode::Replay(replay) => Self::handle_replay(replay.clone(), parts).await,
+        }
+    }
+
+    async fn handle_main(&mut self, p: Vec<&str>) -> CommandOutcome {
+        let input = p.join(" "); // reconstruct full line
+        let parts: Vec<&str> = input.splitn(2, ' ').collect(); // only split once
+
+        if let Some(outcome) = view_command(parts[0], parts.get(1).copied().unwrap_or_default()) {
+            return outcome;
+        }
+
+        match parts[0].to_lowercase().as_str() {
+            "new" => {
+                match parse_new_universe(parts.get(1).copied().unwrap_or_default()) {
+                    Ok((name, options)) => self.client.new_universe(name, options).await,
+                    Err(e) => Log::info(e),
+                }
+            }
+            "manage" => {
+                if parts.len() > 1 {
+                    let name = parts[1].trim().to_string();
+
+                    if !self.client.does_universe_exist(name.clone()).await {
+                        Log::info(format!("'{}' not found", name));
+                        return CommandOutcome::Continue;
+                    }
+
+                    Log::info(format!("Now managing '{}'", name));
+                    self.mode = UiMode::Manage { name };
+                } else {
+                    Log::info("Usage: manage <name>");
+                }
+            }
+            "list" => {
+                let list = self.client.list_universes().await;
+                Log::info(format!("Universes: {:?}", list));
+            }
+            "scoreboard" => {
+                let Some(path) = parts.get(1).map(|p| PathBuf::from(p.trim())) else {
+                    return CommandOutcome::Scoreboard;
+                };
+                let board = self.client.scoreboard().await;
+                match write_scoreboard(&path, &board).await {
+                    Ok(()) => Log::info(format!("Scoreboard written to {}", path.display())),
+                    Err(e) => Log::info(format!("Scoreboard not written: {}", e)),
+                }
+            }
+            "factions" => {
+                let factions = self.client.factions().await;
+                if factions.is_empty() {
+                    Log::info("No factions, every universe fights for itself");
+                }
+                for (faction, members) in factions {
+                    Log::info(format!("Faction {}: {}", faction, members.join(", ")));
+                }
+            }
+            "faction" => {
+                let args: Vec<&str> = parts.get(1).map(|a| a.split_whitespace().collect()).unwrap_or_default();
+                let [faction, action] = args[..] else {
+                    Log::info("Usage: faction <name> pause|resume|heal");
+                    return CommandOutcome::Continue;
+                };
+                let faction = faction.to_lowercase();
+
+                let Some(members) = self.client.factions().await.remove(&faction) else {
+                    Log::info(format!("No living universe in faction '{}'", faction));
+                    return CommandOutcome::Continue;
+                };
+                let (command, verb): (fn() -> UniverseCommand, _) = match action.to_lowercase().as_str() {
+                    "pause" => (|| UniverseCommand::Stop, "Pausing"),
+                    "resume" => (|| UniverseCommand::Start, "Resuming"),
+                    "heal" => (|| UniverseCommand::InjectEvent(UniverseEvent::Heal(20)), "Healing"),
+                    _ => {
+                        Log::info("Usage: faction <name> pause|resume|heal");
+                        return CommandOutcome::Continue;
+                    }
+                };
+
+                Log::info(format!("{} faction {}: {}", verb, faction, members.join(", ")));
+                for member in members {
+                    self.client.send_universe_command(member, command()).await;
+                }
+            }
+            "queues" => {
+                let metrics = self.client.queue_metrics().await;
+                if metrics.is_empty() {
+                    Log::info("No intent queues, no universes alive");
+                }
+                for (name, m) in metrics {
+                    Log::info(format!(
+                        "{}: {} queued, peak {}, {} processed",
+                        name, m.depth, m.peak_depth, m.processed
+                    ));
+                }
+            }
+            "clock" => {
+                match parts.get(1).map(|mode| mode.trim().parse::<ClockMode>()) {
+                    Some(Ok(mode)) => self.client.set_clock(mode).await,
+                    Some(Err(e)) => Log::info(e),
+                    None => {
+                        if let Some((mode, tick)) = self.client.clock_status().await {
+                            Log::info(format!("Clock: {} at tick {}", mode, tick));
+                        }
+                    }
+                }
+            }
+            "step" => {
+                let ticks = match parts.get(1).map(|n| n.trim().parse::<u64>()) {
+                    None => 1,
+                    Some(Ok(n)) => n,
+                    Some(Err(_)) => {
+                        Log::info("Usage: step [ticks]");
+                        return CommandOutcome::Continue;
+                    }
+                };
+
+                match self.client.clock_status().await {
+                    Some((ClockMode::Stepped, _)) => {
+                        if let Some(tick) = self.client.step(ticks).await {
+                            Log::info(format!("Stepped to tick {}", tick));
+                        }
+                    }
+                    _ => Log::info("Clock is running on its own, switch with 'clock step' first"),
+                }
+            }
+            "save" => {
+                let Some(path) = parts.get(1).map(|p| PathBuf::from(p.trim())) else {
+                    Log::info("Usage: save <file>");
+                    return CommandOutcome::Continue;
+                };
+                match self.client.save(path.clone()).await {
+                    Ok(count) => Log::info(format!("Saved {} universes to {}", count, path.display())),
+                    Err(e) => Log::info(format!("Save failed: {}", e)),
+                }
+            }
+            "load" => {
+                let Some(path) = parts.get(1).map(|p| PathBuf::from(p.trim())) else {
+                    Log::info("Usage: load <file>");
+                    return CommandOutcome::Continue;
+                };
+                match self.client.load(path.clone()).await {
+                    Ok(count) => Log::info(format!("Loaded {} universes from {}", count, path.display())),
+                    Err(e) => Log::info(format!("Load failed: {}", e)),
+                }
+            }
+            "shutdown" => return CommandOutcome::Shutdown,
+            _ => Log::info(format!("Unknown command: '{}'", parts[0]))
+        }
+        CommandOutcome::Continue
+    }
+
+    async fn handle_manage(&mut self, name: String, p: Vec<&str>) -> CommandOutcome {
+        match p[0].to_lowercase().as_str() {
+            "back" => {
+                self.mode = UiMode::Main;
+                return CommandOutcome::CloseInspector;
+            }
+            "state" => return CommandOutcome::Inspect(name),
+            "resume" => {
+                Log::info(format!("Resuming {}", name));
+                self.client.send_universe_command(name.clone(), UniverseCommand::Start).await;
+            }
+            "pause" => {
+                Log::info(format!("Pausing {}", name));
+                self.client.send_universe_command(name.clone(), UniverseCommand::Stop).await;
+            }
+            "event" => self.mode = UiMode::EventMenu { name },
+            "declare-war" | "ally" | "peace" => {
+                let action = match p[0].to_lowercase().as_str() {
+                    "declare-war" => DiplomaticAction::DeclareWar,
+                    "ally" => DiplomaticAction::Ally,
+                    _ => DiplomaticAction::Peace,
+                };
+                if p.len() < 2 {
+                    Log::info(format!("Usage: {} <other>", p[0]));
+                    return CommandOutcome::Continue;
+                }
+
+                if let Err(e) = self.client.send_diplomacy(name, p[1..].join(" "), action).await {
+                    Log::info(format!("Diplomacy failed: {}", e));
+                }
+            }
+            "collapse" => {
+                Log::info(format!("Collapsing {}", name));
+                self.client.send_universe_command(name.clone(), UniverseCommand::Shutdown).await;
+            }
+            _ => Log::info("Unknown manage command"),
+        }
+        CommandOutcome::Continue
+    }
+
+    async fn handle_user_event(&mut self, name: String, p: Vec<&str>) -> CommandOutcome {
+        // get universe for logs
+        let Some(universe) = self.client.universe_ref(name.clone()).await else {
+            Log::info(format!("'{}' no longer exists", name));
+            self.mode = UiMode::Main;
+            return CommandOutcome::CloseInspector;
+        };
+
+        match p[0].to_lowercase().as_str() {
+            "back" => self.mode = UiMode::Manage { name },
+            "shatter" => {
+                let strength = 20;
+
+                Log::user_action("You", "shattered", universe);
+
+                self.client.send_universe_command(
+                    name.clone(),
+                    UniverseCommand::InjectEvent(UniverseEvent::Shatter(strength)),
+                ).await;
+            }
+            "heal" => {
+                let strength = 20;
+
+                Log::user_action("You", "healed", universe);
+
+                self.client.send_universe_command(
+                    name.clone(),
+                    UniverseCommand::InjectEvent(UniverseEvent::Heal(strength)),
+                ).await;
+            }
+            "crash" => {
+                Log::user_action("You", "CRASHED", universe);
+
+                self.client.send_universe_command(
+                    name.clone(),
+                    UniverseCommand::InjectEvent(UniverseEvent::Crash),
+                ).await;
+            }
+            "ping" | "pong" => {
+                let Some(other) = p.get(1).map(|o| o.to_string()) else {
+                    Log::info(format!("Usage: {} <other>", p[0]));
+                    return CommandOutcome::Continue;
+                };
+
+                // ping: this universe pings <other>, pong: <other> pings this universe
+                let (pinger, ponger) = if p[0].eq_ignore_ascii_case("ping") {
+                    (name, other)
+                } else {
+                    (other, name)
+                };
+
+                if let Err(e) = self.client.send_ping(pinger, ponger).await {
+                    Log::info(format!("Ping failed: {}", e));
+                }
+            }
+            _ => Log::info("Unknown event command"),
+        }
+        CommandOutcome::Continue
+    }
+
+    async fn handle_replay(replay: ReplayControl, p: Vec<&str>) -> CommandOutcome {
+        if let Some(outcome) = view_command(p[0], &p[1..].join(" ")) {
+            return outcome;
+        }
+
+        match p[0].to_lowercase().as_str() {
+            "pause" => replay.pause().await,
+            "resume" => replay.resume().await,
+            "speed" => match p.get(1).map(|s| parse_speed_factor(s)) {
+                Some(Ok(speed)) => replay.set_speed(speed).await,
+                Some(Err(e)) => Log::info(e),
+                None => Log::info("Usage: speed <n>x"),
+            },
+            "seek" => match p.get(1).map(|t| t.parse::<u64>()) {
+                Some(Ok(tick)) => replay.seek(tick).await,
+                _ => Log::info("Usage: seek <tick>"),
+            },
+            "status" => {
+                if let Some(status) = replay.status().await {
+                    Log::info(format!(
+                        "Replay {}: tick {} of {}, {}/{} events, {}x",
+                        if status.played == status.total {
+                            "finished"
+                        } else if status.paused {
+                            "paused"
+                        } else {
+                            "playing"
+                        },
+                        status.tick, status.last_tick, status.played, status.total, status.speed,
+                    ));
+                }
+            }
+            "shutdown" => return CommandOutcome::Shutdown,
+            _ => Log::info(format!("Unknown replay command: '{}'", p[0])),
+        }
+        CommandOutcome::Continue
+    }
+}
+
+/// commands that only change what the TUI shows, so they work the same live and in a replay.
+/// `None` when `command` is not one of them.
+fn view_command(command: &str, args: &str) -> Option<CommandOutcome> {
+    let outcome = match command.to_lowercase().as_str() {
+        "leaderboard" => match args.trim() {
+            "" => CommandOutcome::Leaderboard(StatsColumn::default()),
+            column => match column.parse::<StatsColumn>() {
+                Ok(column) => CommandOutcome::Leaderboard(column),
+                Err(e) => {
+                    Log::info(e);
+                    CommandOutcome::Continue
+                }
+            },
+        },
+        "graph" => CommandOutcome::ToggleGraph,
+        "filter" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+            ["off"] => CommandOutcome::FilterLogs(LogFilterChange::Off),
+            ["kind", kind] => {
+                let kind = kind.to_lowercase();
+                if SimulationEvent::KINDS.contains(&kind.as_str()) {
+                    CommandOutcome::FilterLogs(LogFilterChange::Kind(kind))
+                } else {
+                    Log::info(format!("Unknown kind '{}', expected one of {}", kind, SimulationEvent::KINDS.join(", ")));
+                    CommandOutcome::Continue
+                }
+            }
+            ["universe", ref name @ ..] if !name.is_empty() => CommandOutcome::FilterLogs(LogFilterChange::Universe(name.join(" "))),
+            _ => {
+                Log::info("Usage: filter kind <kind>|universe <name>|off");
+                CommandOutcome::Continue
+            }
+        },
+        _ => return None,
+    };
+    Some(outcome)
+}
+
+/// `new <name> [--targeting <strategy>] [--script <file>] [--faction <name>]`, the name may contain spaces
+fn parse_new_universe(args: &str) -> Result<(String, UniverseOptions), String> {
+    let mut tokens = args.split_whitespace();
+    let mut name = Vec::new();
+    let mut options = UniverseOptions::default();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "--targeting" => {
+                let strategy = tokens.next().ok_or("--targeting needs a strategy")?;
+                options.targeting = strategy.parse()?;
+            }
+            "--script" => {
+                let path = tokens.next().ok_or("--script needs a file")?;
+                options.script = Some(PathBuf::from(path));
+            }
+            "--faction" => {
+                let faction = tokens.next().ok_or("--faction needs a name")?;
+                options.faction = Some(faction.to_lowercase());
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            word => name.push(word),
+        }
+    }
+
+    if name.is_empty() {
+        return Err("Usage: new <name> [--targeting <strategy>] [--script <file>] [--faction <name>]".to_owned());
+    }
+    Ok((name.join(" "), options))
+}
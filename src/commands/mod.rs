@@ -0,0 +1,3 @@
+﻿pub mod interpreter;
+
+pub use interpreter::{CommandInterpreter, CommandOutcome, LogFilterChange};
@@ -0,0 +1,154 @@
+﻿use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use crate::commands::{CommandInterpreter, CommandOutcome};
+use crate::logging::{subscribe, LoggedEvent};
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::combat_stats::StatsColumn;
+use crate::supervisor::scoreboard::scoreboard_lines;
+use crate::supervisor::user_supervisor::UserSupervisor;
+
+/// front-end without a terminal UI: commands come line by line from stdin or a script,
+/// logs go to stdout as plain text.
+pub struct HeadlessCli<'a> {
+    supervisor: &'a mut UserSupervisor,
+    commands: CommandInterpreter,
+    log_receiver: Option<broadcast::Receiver<LoggedEvent>>,
+}
+
+impl<'a> HeadlessCli<'a> {
+    pub fn new(supervisor: &'a mut UserSupervisor, commands: CommandInterpreter) -> Self {
+        Self {
+            supervisor,
+            commands,
+            log_receiver: Some(subscribe()),
+        }
+    }
+
+    /// runs until the input ends or a `shutdown` command, then collapses every universe
+    pub async fn run(&mut self, script: Option<PathBuf>) -> io::Result<()> {
+        let input: Box<dyn AsyncBufRead + Unpin + Send> = match script {
+            Some(path) => match File::open(&path).await {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(e) => {
+                    self.supervisor.shut_down_all().await;
+                    return Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+                }
+            },
+            None => Box::new(BufReader::new(tokio::io::stdin())),
+        };
+
+        let log_receiver = self.log_receiver.take().unwrap_or_else(subscribe);
+        let (stop_tx, printer) = spawn_log_printer(log_receiver);
+
+        let result = self.read_commands(input).await;
+
+        // end of program, shutdown all universes and print what they logged on the way out
+        self.supervisor.shut_down_all().await;
+        let _ = stop_tx.send(());
+        let _ = printer.await;
+
+        result
+    }
+
+    async fn read_commands(&mut self, input: Box<dyn AsyncBufRead + Unpin + Send>) -> io::Result<()> {
+        let mut lines = input.lines();
+
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // headless only: let the simulation run for a while
+            if let Some(rest) = line.strip_prefix("wait") {
+                match rest.trim().parse::<u64>() {
+                    Ok(ms) => tokio::time::sleep(Duration::from_millis(ms)).await,
+                    Err(_) => Log::info("Usage: wait <milliseconds>"),
+                }
+                continue;
+            }
+
+            match self.commands.execute(line).await {
+                CommandOutcome::Continue | CommandOutcome::CloseInspector => {}
+                CommandOutcome::ToggleGraph => Log::info("No graph without the terminal UI"),
+                CommandOutcome::FilterLogs(_) => Log::info("No log filters without the terminal UI"),
+                CommandOutcome::Inspect(name) => self.print_state(name).await,
+                CommandOutcome::Scoreboard => self.print_scoreboard().await,
+                CommandOutcome::Leaderboard(column) => self.print_leaderboard(column).await,
+                CommandOutcome::Shutdown => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn print_scoreboard(&self) {
+        let board = self.supervisor.client.scoreboard().await;
+        if board.is_empty() {
+            Log::info("No scores yet");
+            return;
+        }
+        for line in scoreboard_lines(&board) {
+            Log::info(line);
+        }
+    }
+
+    async fn print_leaderboard(&self, column: StatsColumn) {
+        let mut stats = self.supervisor.client.combat_stats().await;
+        column.sort(&mut stats);
+        for s in stats {
+            Log::info(format!(
+                "{}: dealt {}, taken {}, heals {}, kills {}, uptime {}{}",
+                s.universe.name, s.damage_dealt, s.damage_taken, s.heals_given, s.kills, s.uptime,
+                if s.fell_at.is_some() { " (fallen)" } else { "" },
+            ));
+        }
+    }
+
+    /// no inspector panel here, the state is logged once instead
+    async fn print_state(&self, name: String) {
+        match self.supervisor.client.query_universe_state(name).await {
+            Ok(report) => Log::info(format!(
+                "{} (#{}): hp {}/100, tick {}, {}, tactics {}, faction {}, enemies [{}], brothers [{}], sees [{}]",
+                report.name, report.id, report.hp, report.tick, report.status(), report.tactics(),
+                report.faction.as_deref().unwrap_or("none"),
+                report.enemies.join(", "), report.brothers.join(", "), report.sees_text(),
+            )),
+            Err(e) => Log::info(format!("State unavailable: {}", e)),
+        }
+    }
+}
+
+/// prints every event to stdout as plain text until told to stop, then prints what is left
+fn spawn_log_printer(mut log_receiver: broadcast::Receiver<LoggedEvent>) -> (oneshot::Sender<()>, JoinHandle<()>) {
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+
+    let printer = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                line = log_receiver.recv() => match line {
+                    Ok(entry) if entry.event.is_quiet() => {}
+                    Ok(entry) => println!("{}", entry.event),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        println!("... {} log lines dropped", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = &mut stop_rx => break,
+            }
+        }
+
+        while let Ok(entry) = log_receiver.try_recv() {
+            if !entry.event.is_quiet() {
+                println!("{}", entry.event);
+            }
+        }
+    });
+
+    (stop_tx, printer)
+}
@@ -0,0 +1,3 @@
+﻿pub mod headless;
+
+pub use headless::HeadlessCli;
@@ -0,0 +1,163 @@
+﻿use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use crate::logging::{subscribe_lossless, unsubscribe_lossless, LoggedEvent};
+
+pub const DEFAULT_JOURNAL_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// appends every logged event to disk, one JSON object per line. it reads the lossless feed,
+/// not the bus, so a slow disk never costs it events.
+/// when the file grows past `max_bytes` it is moved to `<path>.1`, `<path>.2`, ... (oldest first).
+/// a journal holds a single run, one left at the same path by an earlier run is removed.
+pub struct Journal {
+    stop_tx: oneshot::Sender<()>,
+    task: JoinHandle<io::Result<()>>,
+}
+
+impl Journal {
+    /// subscribes right away, events logged after this returns are all written
+    pub async fn start(path: PathBuf, max_bytes: u64) -> io::Result<Journal> {
+        let log_receiver = subscribe_lossless();
+        let writer = JournalWriter::open(path, max_bytes).await?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let task = tokio::spawn(writer.run(log_receiver, stop_rx));
+
+        Ok(Journal { stop_tx, task })
+    }
+
+    /// writes whatever was logged so far and closes the file
+    pub async fn finish(self) -> io::Result<()> {
+        unsubscribe_lossless();
+        let _ = self.stop_tx.send(());
+        self.task.await.map_err(io::Error::other)?
+    }
+}
+
+struct JournalWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    file: BufWriter<File>,
+    written: u64,
+    rotations: u32,
+}
+
+impl JournalWriter {
+    async fn open(path: PathBuf, max_bytes: u64) -> io::Result<JournalWriter> {
+        // replaying it would play both runs over each other
+        for old in journal_files(&path).await {
+            match fs::remove_file(&old).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        let (file, written) = open_append(&path).await?;
+
+        Ok(JournalWriter { path, max_bytes, file, written, rotations: 0 })
+    }
+
+    async fn run(mut self, mut log_receiver: mpsc::UnboundedReceiver<LoggedEvent>, mut stop_rx: oneshot::Receiver<()>) -> io::Result<()> {
+        loop {
+            tokio::select! {
+                entry = log_receiver.recv() => match entry {
+                    Some(entry) => self.write(&entry).await?,
+                    None => break,
+                },
+                _ = &mut stop_rx => break,
+            }
+
+            // flush once the burst is over, not on every line
+            if log_receiver.is_empty() {
+                self.file.flush().await?;
+            }
+        }
+
+        while let Ok(entry) = log_receiver.try_recv() {
+            self.write(&entry).await?;
+        }
+        self.file.flush().await?;
+        self.file.get_mut().sync_all().await
+    }
+
+    async fn write(&mut self, entry: &LoggedEvent) -> io::Result<()> {
+        let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        self.write_line(line).await
+    }
+
+    async fn write_line(&mut self, line: String) -> io::Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate().await?;
+        }
+
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.write_all(b"\n").await?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    async fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush().await?;
+
+        self.rotations += 1;
+        fs::rename(&self.path, rotated_path(&self.path, self.rotations)).await?;
+
+        let (file, written) = open_append(&self.path).await?;
+        self.file = file;
+        self.written = written;
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path).await?;
+    let written = file.metadata().await?.len();
+    Ok((BufWriter::new(file), written))
+}
+
+fn rotated_path(path: &Path, index: u32) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+/// every file of a journal, rotated ones first, oldest to newest
+pub async fn journal_files(path: &Path) -> Vec<PathBuf> {
+    let rotations = count_rotations(path).await;
+    (1..=rotations)
+        .map(|index| rotated_path(path, index))
+        .chain(std::iter::once(path.to_path_buf()))
+        .collect()
+}
+
+/// how many rotated files the journal has, they are numbered without gaps
+async fn count_rotations(path: &Path) -> u32 {
+    let mut index = 0;
+    while fs::try_exists(rotated_path(path, index + 1)).await.unwrap_or(false) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn earlier_run_is_replaced() {
+        let path = std::env::temp_dir().join(format!("journal-{}.jsonl", std::process::id()));
+        std::fs::write(&path, "earlier run\n").unwrap();
+        std::fs::write(rotated_path(&path, 1), "even earlier run\n").unwrap();
+        std::fs::write(rotated_path(&path, 2), "earlier run\n").unwrap();
+
+        Journal::start(path.clone(), DEFAULT_JOURNAL_MAX_BYTES).await.unwrap().finish().await.unwrap();
+
+        let files = journal_files(&path).await;
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(files, [path]);
+        assert!(!contents.contains("earlier run"));
+    }
+}
@@ -0,0 +1,90 @@
+﻿use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+pub mod simulation_event;
+pub mod journal;
+
+pub use simulation_event::{FactionRef, SimulationEvent, UniverseRef, UniverseScore};
+
+/// what travels on the log bus: an event, when it happened and at which simulation tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub timestamp: DateTime<Local>,
+    pub tick: u64,
+    #[serde(flatten)]
+    pub event: SimulationEvent,
+}
+
+impl LoggedEvent {
+    /// an event stamped with the current time and tick
+    pub fn now(event: SimulationEvent) -> LoggedEvent {
+        LoggedEvent {
+            timestamp: Local::now(),
+            tick: CURRENT_TICK.load(Ordering::Relaxed),
+            event,
+        }
+    }
+}
+
+/// events the bus holds for its slowest subscriber, older ones are dropped for it
+pub const LOG_BUS_CAPACITY: usize = 4096;
+
+lazy_static::lazy_static! {
+    static ref LOG_TX: broadcast::Sender<LoggedEvent> = broadcast::channel(LOG_BUS_CAPACITY).0;
+    /// the journal's own feed, unbounded so it never misses an event the way a lagging bus subscriber can
+    static ref LOSSLESS_TX: Mutex<Option<mpsc::UnboundedSender<LoggedEvent>>> = Mutex::new(None);
+}
+
+/// tick of the simulation clock, stamped on every event
+static CURRENT_TICK: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_current_tick(tick: u64) {
+    CURRENT_TICK.store(tick, Ordering::Relaxed);
+}
+
+/// the tick of the run, or of the replay, as far as the log bus knows
+pub fn current_tick() -> u64 {
+    CURRENT_TICK.load(Ordering::Relaxed)
+}
+
+pub fn log(event: SimulationEvent) {
+    publish(LoggedEvent::now(event));
+}
+
+/// puts an event recorded earlier back on the bus, keeping its timestamp and tick
+pub fn republish(entry: LoggedEvent) {
+    set_current_tick(entry.tick);
+    publish(entry);
+}
+
+fn publish(entry: LoggedEvent) {
+    if let Some(lossless_tx) = LOSSLESS_TX.lock().unwrap().as_ref() {
+        let _ = lossless_tx.send(entry.clone());
+    }
+    let _ = LOG_TX.send(entry);
+}
+
+pub fn subscribe() -> broadcast::Receiver<LoggedEvent> {
+    LOG_TX.subscribe()
+}
+
+/// events on the bus that some subscriber has not read yet
+pub fn backlog() -> usize {
+    LOG_TX.len()
+}
+
+/// every event from now on, none dropped however far behind the receiver is.
+/// there is one such feed, subscribing again takes it over.
+pub fn subscribe_lossless() -> mpsc::UnboundedReceiver<LoggedEvent> {
+    let (lossless_tx, lossless_rx) = mpsc::unbounded_channel();
+    *LOSSLESS_TX.lock().unwrap() = Some(lossless_tx);
+    lossless_rx
+}
+
+/// ends the lossless feed, its receiver gets what was sent so far and then `None`
+pub fn unsubscribe_lossless() {
+    *LOSSLESS_TX.lock().unwrap() = None;
+}
@@ -0,0 +1,196 @@
+﻿use std::fmt;
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use crate::universe::{DiplomaticAction, Relationship, UniverseId, UniverseStatus};
+
+/// a universe as it appears in an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniverseRef {
+    pub id: UniverseId,
+    pub name: String,
+    pub color: RGB8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faction: Option<FactionRef>,
+}
+
+/// a faction as it appears in an event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FactionRef {
+    pub name: String,
+    pub color: RGB8,
+}
+
+/// how one universe did in a match, as it appears on the scoreboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniverseScore {
+    pub id: UniverseId,
+    pub name: String,
+    pub faction: Option<String>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub heals_given: i32,
+    pub kills: u32,
+    pub joined_at: u64,
+    pub fell_at: Option<u64>,
+    pub survival_ticks: u64, // up to its fall, or to when the board was made
+}
+
+/// everything worth telling about a run. how it is shown is up to each subscriber.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimulationEvent {
+    Created { universe: UniverseRef },
+    RelationshipSet {
+        first: UniverseRef,
+        second: UniverseRef,
+        relationship: Relationship,
+        #[serde(default)] // journals from before affinities
+        affinity: i32,
+    },
+    Attack { source: UniverseRef, target: UniverseRef, damage: i32 },
+    Heal { source: UniverseRef, target: UniverseRef, amount: i32 },
+    Collapsed { universe: UniverseRef },
+    Status { universe: UniverseRef, hp: i32, status: UniverseStatus }, // hp or status changed, for the overview
+    Pong { source: UniverseRef, target: UniverseRef, round_trip_us: u64 },
+    UserAction { actor: String, action: String, target: UniverseRef },
+    Rejected { source: UniverseRef, intent: String, reason: String },
+    Diplomacy { source: UniverseRef, target: UniverseRef, action: DiplomaticAction, accepted: bool },
+    FactionVictory { faction: FactionRef, survivors: Vec<UniverseRef> }, // every other faction is gone
+    MatchOver { winners: Vec<UniverseRef>, scoreboard: Vec<UniverseScore> }, // no hostile pair is left alive
+    Info { message: String },
+    Rewound { tick: u64 }, // a replay jumped back, everything shown so far is void
+    Reset { tick: u64 },   // a loaded snapshot replaced every universe, the ones before are gone
+}
+
+impl SimulationEvent {
+    /// every `kind` an event can have, as written to the journal
+    pub const KINDS: [&'static str; 15] = [
+        "created", "relationship_set", "attack", "heal", "collapsed", "status", "pong", "user_action",
+        "rejected", "diplomacy", "faction_victory", "match_over", "info", "rewound", "reset",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SimulationEvent::Created { .. } => "created",
+            SimulationEvent::RelationshipSet { .. } => "relationship_set",
+            SimulationEvent::Attack { .. } => "attack",
+            SimulationEvent::Heal { .. } => "heal",
+            SimulationEvent::Collapsed { .. } => "collapsed",
+            SimulationEvent::Status { .. } => "status",
+            SimulationEvent::Pong { .. } => "pong",
+            SimulationEvent::UserAction { .. } => "user_action",
+            SimulationEvent::Rejected { .. } => "rejected",
+            SimulationEvent::Diplomacy { .. } => "diplomacy",
+            SimulationEvent::FactionVictory { .. } => "faction_victory",
+            SimulationEvent::MatchOver { .. } => "match_over",
+            SimulationEvent::Info { .. } => "info",
+            SimulationEvent::Rewound { .. } => "rewound",
+            SimulationEvent::Reset { .. } => "reset",
+        }
+    }
+
+    /// bookkeeping for the panels, log printers leave it out unless asked for it
+    pub fn is_quiet(&self) -> bool {
+        matches!(self, SimulationEvent::Status { .. })
+    }
+
+    /// the universes the event is about
+    pub fn universes(&self) -> Vec<&UniverseRef> {
+        match self {
+            SimulationEvent::Created { universe }
+            | SimulationEvent::Collapsed { universe }
+            | SimulationEvent::Status { universe, .. } => vec![universe],
+            SimulationEvent::RelationshipSet { first, second, .. } => vec![first, second],
+            SimulationEvent::Attack { source, target, .. }
+            | SimulationEvent::Heal { source, target, .. }
+            | SimulationEvent::Pong { source, target, .. }
+            | SimulationEvent::Diplomacy { source, target, .. } => vec![source, target],
+            SimulationEvent::UserAction { target, .. } => vec![target],
+            SimulationEvent::Rejected { source, .. } => vec![source],
+            SimulationEvent::FactionVictory { survivors, .. } => survivors.iter().collect(),
+            SimulationEvent::MatchOver { winners, .. } => winners.iter().collect(),
+            SimulationEvent::Info { .. } | SimulationEvent::Rewound { .. } | SimulationEvent::Reset { .. } => vec![],
+        }
+    }
+}
+
+/// how a relationship is announced: label and subtitle
+pub fn relationship_wording(relationship: Relationship) -> (&'static str, &'static str) {
+    match relationship {
+        Relationship::Enemy => ("ENEMIES", "⚔️ War has been declared"),
+        Relationship::Rival => ("RIVALS", "Blades drawn, not yet swung"),
+        Relationship::Neutral => ("NEUTRAL", "They look the other way"),
+        Relationship::Ally => ("ALLIES", "A cautious handshake"),
+        Relationship::Brother => ("BROTHERS", "An alliance forged in the void"),
+    }
+}
+
+/// plain text version, for subscribers without colours
+impl fmt::Display for SimulationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationEvent::Created { universe } => match &universe.faction {
+                Some(faction) => write!(f, "> Created universe {} for faction {}", universe.name, faction.name),
+                None => write!(f, "> Created universe {}", universe.name),
+            },
+            SimulationEvent::RelationshipSet { first, second, relationship, affinity } => {
+                let (label, subtitle) = relationship_wording(*relationship);
+                write!(f, "Relationship: {} and {} are {} ({:+}) {}", first.name, second.name, label, affinity, subtitle)
+            }
+            SimulationEvent::Attack { source, target, damage } => {
+                write!(f, "[{}] → [{}] −{} HP", source.name, target.name, damage)
+            }
+            SimulationEvent::Heal { source, target, amount } => {
+                write!(f, "[{}] healed [{}] +{} HP", source.name, target.name, amount)
+            }
+            SimulationEvent::Collapsed { universe } => write!(f, "☠ {} has COLLAPSED", universe.name),
+            SimulationEvent::Status { universe, hp, status } => write!(f, "{}: {} hp, {}", universe.name, hp, status),
+            SimulationEvent::Pong { source, target, round_trip_us } => {
+                write!(f, "[{}] ping ⇄ pong [{}] {}", source.name, target.name, format_round_trip(*round_trip_us))
+            }
+            SimulationEvent::UserAction { actor, action, target } => {
+                write!(f, "> {} {} {}", actor, action, target.name)
+            }
+            SimulationEvent::Rejected { source, intent, reason } => {
+                write!(f, "✋ [{}] {} rejected: {}", source.name, intent, reason)
+            }
+            SimulationEvent::Diplomacy { source, target, action, accepted } => {
+                write!(f, "🕊 [{}] {} [{}]", source.name, diplomacy_wording(*action, *accepted), target.name)
+            }
+            SimulationEvent::FactionVictory { faction, survivors } => {
+                let names: Vec<&str> = survivors.iter().map(|u| u.name.as_str()).collect();
+                write!(f, "🏆 Faction {} wins! Standing: {}", faction.name, names.join(", "))
+            }
+            SimulationEvent::MatchOver { winners, .. } => {
+                let names: Vec<&str> = winners.iter().map(|u| u.name.as_str()).collect();
+                if names.is_empty() {
+                    write!(f, "🏁 The match is over, nobody is left standing")
+                } else {
+                    write!(f, "🏁 The match is over! Winners: {}", names.join(", "))
+                }
+            }
+            SimulationEvent::Info { message } => write!(f, "> {}", message),
+            SimulationEvent::Rewound { tick } => write!(f, "⏪ Replay rewound to tick {}", tick),
+            SimulationEvent::Reset { tick } => write!(f, "> Multiverse replaced by a snapshot at tick {}", tick),
+        }
+    }
+}
+
+/// what came of a diplomatic move, shared by every log printer
+pub fn diplomacy_wording(action: DiplomaticAction, accepted: bool) -> &'static str {
+    match (action, accepted) {
+        (DiplomaticAction::DeclareWar, _) => "declared war on",
+        (DiplomaticAction::Ally, true) => "formed an alliance with",
+        (DiplomaticAction::Ally, false) => "was turned down for an alliance by",
+        (DiplomaticAction::Peace, true) => "made peace with",
+        (DiplomaticAction::Peace, false) => "was refused peace by",
+    }
+}
+
+pub fn format_round_trip(round_trip_us: u64) -> String {
+    if round_trip_us < 1000 {
+        format!("{}µs", round_trip_us)
+    } else {
+        format!("{:.2}ms", round_trip_us as f64 / 1000.0)
+    }
+}
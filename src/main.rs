@@ -1,15 +1,92 @@
+#![allow(clippy::module_inception)]
+
 mod universe;
 mod supervisor;
 mod terminal_ui;
 mod logging;
+mod commands;
+mod headless;
+mod cli;
+mod simulation;
+mod replay;
 
+use clap::Parser;
+use cli::Args;
+use commands::CommandInterpreter;
+use logging::journal::Journal;
+use simulation::SimulationSeed;
+use headless::HeadlessCli;
+use replay::Replay;
+use simulation::ClockMode;
 use terminal_ui::TerminalUI;
 use supervisor::user_supervisor::UserSupervisor;
 
 #[tokio::main]
 async fn main() {
-    let mut user_supervisor = UserSupervisor::new();
-    let mut ui = TerminalUI::new(&mut user_supervisor);
+    let args = Args::parse();
+
+    // journal first, so it sees every event of the run
+    let journal = match &args.journal {
+        Some(path) => match Journal::start(path.clone(), args.journal_max_bytes).await {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                eprintln!("Cannot open journal {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // a replay only needs the recorded events, the supervisor stays idle without universes
+    let replay = match &args.replay {
+        Some(path) => match Replay::open(path).await {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                eprintln!("Cannot replay {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let clock = if replay.is_some() { ClockMode::Stepped } else { args.clock };
+
+    let seed = args.seed.map(SimulationSeed).unwrap_or_else(SimulationSeed::random);
+    let mut user_supervisor = UserSupervisor::new(seed, clock, args.perception(), args.scoreboard.clone());
+
+    let client = user_supervisor.client.clone();
+    let replay = replay.map(Replay::spawn);
+    let commands = match &replay {
+        Some(replay) => CommandInterpreter::replaying(client, replay.clone()),
+        None => CommandInterpreter::new(client),
+    };
+
+    let mut failed = false;
+    if args.is_headless() {
+        let mut cli = HeadlessCli::new(&mut user_supervisor, commands);
+        if let Some(replay) = &replay {
+            replay.resume().await;
+        }
+        if let Err(e) = cli.run(args.script.clone()).await {
+            eprintln!("Headless run failed: {}", e);
+            failed = true;
+        }
+    } else {
+        let mut ui = TerminalUI::new(&mut user_supervisor, commands);
+        if let Some(replay) = &replay {
+            replay.resume().await;
+        }
+
+        ui.run().await;
+    }
+
+    // every universe is shut down by now, write the last events out
+    if let Some(journal) = journal
+        && let Err(e) = journal.finish().await {
+        eprintln!("Journal incomplete: {}", e);
+        failed = true;
+    }
 
-    ui.run().await;
+    if failed {
+        std::process::exit(1);
+    }
 }
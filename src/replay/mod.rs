@@ -0,0 +1,5 @@
+﻿pub mod replay;
+pub mod replay_control;
+
+pub use replay::Replay;
+pub use replay_control::{ReplayControl, ReplayStatus};
@@ -0,0 +1,202 @@
+﻿use std::io;
+use std::path::Path;
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, sleep_until, Instant};
+use crate::logging::journal::journal_files;
+use crate::logging::{self, LoggedEvent, SimulationEvent};
+use crate::replay::replay_control::{ReplayCommand, ReplayControl};
+use crate::replay::ReplayStatus;
+use crate::supervisor::log_messages::Log;
+
+/// events a seek puts on the bus at once
+const SEEK_BATCH: usize = 256;
+/// a seek waits while the bus holds more unread events than this, so nobody lags behind
+const SEEK_BACKLOG: usize = logging::LOG_BUS_CAPACITY / 4;
+/// how long a seek waits on a subscriber that does not read, before going on anyway
+const SEEK_PATIENCE: Duration = Duration::from_secs(1);
+
+/// a recorded journal, played back onto the log bus as if the run was live.
+/// no universe runs meanwhile, subscribers only see the recorded events.
+pub struct Replay {
+    entries: Vec<LoggedEvent>,
+    played: usize,
+    speed: f64,
+    paused: bool,
+    due: Option<Instant>, // when the next event goes out, none while paused or done
+    skipped: usize,       // journal lines that were not events, told when playback starts
+}
+
+impl Replay {
+    /// reads the journal and its rotated files, oldest first.
+    /// lines that are not events are skipped.
+    pub async fn open(path: &Path) -> io::Result<Replay> {
+        let mut entries = Vec::new();
+        let mut skipped = 0;
+
+        for file in journal_files(path).await {
+            let content = fs::read_to_string(&file).await
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))?;
+
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str::<LoggedEvent>(line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(_) => skipped += 1,
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} holds no events", path.display()),
+            ));
+        }
+
+        Ok(Replay {
+            entries,
+            played: 0,
+            speed: 1.0,
+            paused: true,
+            due: None,
+            skipped,
+        })
+    }
+
+    /// starts paused, so front-ends can subscribe before the first event goes out
+    pub fn spawn(self) -> ReplayControl {
+        let (command_tx, command_rx) = mpsc::channel(32);
+        tokio::spawn(self.run(command_rx));
+        ReplayControl::new(command_tx)
+    }
+
+    async fn run(mut self, mut command_rx: mpsc::Receiver<ReplayCommand>) {
+        loop {
+            let due = self.due;
+
+            tokio::select! {
+                command = command_rx.recv() => match command {
+                    Some(command) => self.handle(command).await,
+                    None => return, // nobody left to watch
+                },
+                _ = sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => self.play_next(),
+            }
+        }
+    }
+
+    async fn handle(&mut self, command: ReplayCommand) {
+        match command {
+            ReplayCommand::Pause => {
+                self.paused = true;
+                self.due = None;
+                Log::info(format!("Replay paused at tick {}", self.tick()));
+            }
+            ReplayCommand::Resume => {
+                self.paused = false;
+                self.schedule_next();
+                if self.played == 0 {
+                    Log::info(format!(
+                        "Replaying {} events, ticks 0 to {}",
+                        self.entries.len(), self.last_tick()
+                    ));
+                    if self.skipped > 0 {
+                        Log::info(format!("Replay skipped {} journal lines that are not events", self.skipped));
+                    }
+                } else {
+                    Log::info(format!("Replay resumed at {}x", self.speed));
+                }
+            }
+            ReplayCommand::SetSpeed(speed) => {
+                self.speed = speed;
+                if !self.paused {
+                    self.schedule_next();
+                }
+                Log::info(format!("Replay speed {}x", speed));
+            }
+            ReplayCommand::Seek(tick) => {
+                self.seek(tick).await;
+                Log::info(format!("Replay at tick {}", tick.min(self.last_tick())));
+            }
+            ReplayCommand::Status { reply } => {
+                let _ = reply.send(ReplayStatus {
+                    played: self.played,
+                    total: self.entries.len(),
+                    tick: self.tick(),
+                    last_tick: self.last_tick(),
+                    speed: self.speed,
+                    paused: self.paused,
+                });
+            }
+        }
+    }
+
+    fn play_next(&mut self) {
+        self.emit(self.played);
+        self.played += 1;
+
+        if self.played == self.entries.len() {
+            self.due = None;
+            Log::info("Replay finished, seek to watch again");
+        } else {
+            self.due = Some(Instant::now() + self.gap_before(self.played));
+        }
+    }
+
+    /// plays everything before `tick` as fast as subscribers keep up.
+    /// jumping back tells subscribers to forget what they saw, then fast-forwards from the start
+    async fn seek(&mut self, tick: u64) {
+        let target = self.entries.iter()
+            .position(|entry| entry.tick >= tick)
+            .unwrap_or(self.entries.len());
+
+        if target < self.played {
+            logging::log(SimulationEvent::Rewound { tick });
+            self.played = 0;
+        }
+
+        while self.played < target {
+            self.wait_for_subscribers().await;
+            let end = (self.played + SEEK_BATCH).min(target);
+            for index in self.played..end {
+                self.emit(index);
+            }
+            self.played = end;
+        }
+
+        if !self.paused {
+            self.schedule_next();
+        }
+    }
+
+    /// the bus drops what a slow subscriber has not read, so a batch only goes out once there is room
+    async fn wait_for_subscribers(&self) {
+        let started = Instant::now();
+        while logging::backlog() > SEEK_BACKLOG && started.elapsed() < SEEK_PATIENCE {
+            sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    fn schedule_next(&mut self) {
+        self.due = (self.played < self.entries.len()).then(Instant::now);
+    }
+
+    /// the recorded time between an event and the one before it, at the current speed
+    fn gap_before(&self, index: usize) -> Duration {
+        let gap = self.entries[index].timestamp - self.entries[index - 1].timestamp;
+        gap.to_std().unwrap_or_default().div_f64(self.speed)
+    }
+
+    fn emit(&self, index: usize) {
+        logging::republish(self.entries[index].clone());
+    }
+
+    /// tick of the last event played
+    fn tick(&self) -> u64 {
+        self.played.checked_sub(1).map_or(0, |index| self.entries[index].tick)
+    }
+
+    fn last_tick(&self) -> u64 {
+        self.entries.last().map_or(0, |entry| entry.tick)
+    }
+}
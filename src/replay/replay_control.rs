@@ -0,0 +1,64 @@
+﻿use tokio::sync::{mpsc, oneshot};
+
+pub(crate) enum ReplayCommand {
+    Pause,
+    Resume,
+    SetSpeed(f64),
+    Seek(u64),
+    Status { reply: oneshot::Sender<ReplayStatus> },
+}
+
+/// where the playback stands
+#[derive(Debug, Clone)]
+pub struct ReplayStatus {
+    pub played: usize,
+    pub total: usize,
+    pub tick: u64,
+    pub last_tick: u64,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+/// cheap, cloneable remote of a running replay.
+/// the replay stops once every copy is dropped.
+#[derive(Clone)]
+pub struct ReplayControl {
+    command_tx: mpsc::Sender<ReplayCommand>,
+}
+
+impl ReplayControl {
+    pub(crate) fn new(command_tx: mpsc::Sender<ReplayCommand>) -> Self {
+        ReplayControl { command_tx }
+    }
+
+    async fn send(&self, command: ReplayCommand) {
+        let _ = self.command_tx.send(command).await;
+    }
+
+    async fn request<T>(&self, build: impl FnOnce(oneshot::Sender<T>) -> ReplayCommand) -> Option<T> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(build(reply_tx)).await;
+        reply_rx.await.ok()
+    }
+
+    pub async fn pause(&self) {
+        self.send(ReplayCommand::Pause).await;
+    }
+
+    pub async fn resume(&self) {
+        self.send(ReplayCommand::Resume).await;
+    }
+
+    pub async fn set_speed(&self, speed: f64) {
+        self.send(ReplayCommand::SetSpeed(speed)).await;
+    }
+
+    /// jumps to the start of `tick`, the replay tells where it landed once the events are out
+    pub async fn seek(&self, tick: u64) {
+        self.send(ReplayCommand::Seek(tick)).await;
+    }
+
+    pub async fn status(&self) -> Option<ReplayStatus> {
+        self.request(|reply| ReplayCommand::Status { reply }).await
+    }
+}
@@ -0,0 +1,160 @@
+﻿use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+use crate::logging;
+
+/// one simulation tick in real time
+pub const TICK_PERIOD: Duration = Duration::from_millis(80);
+
+/// how fast simulation time flows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockMode {
+    RealTime,
+    Accelerated(f64), // real time times this factor
+    Stepped,          // only moves when told to, tick by tick
+}
+
+impl ClockMode {
+    fn period(&self) -> Option<Duration> {
+        match self {
+            ClockMode::RealTime => Some(TICK_PERIOD),
+            ClockMode::Accelerated(factor) => Some(TICK_PERIOD.div_f64(*factor)),
+            ClockMode::Stepped => None,
+        }
+    }
+}
+
+impl FromStr for ClockMode {
+    type Err = String;
+
+    /// `real`, `step` or a speed factor like `10x`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "real" | "1x" => Ok(ClockMode::RealTime),
+            "step" => Ok(ClockMode::Stepped),
+            other => parse_speed_factor(other).map(ClockMode::Accelerated),
+        }
+    }
+}
+
+/// `2x`, `0.5x` or just `4`, for the clock and for replays
+pub fn parse_speed_factor(s: &str) -> Result<f64, String> {
+    let factor = s.strip_suffix('x').unwrap_or(s).parse::<f64>()
+        .map_err(|_| format!("'{}' is not a speed factor", s))?;
+    if !(factor > 0.0 && factor <= 1000.0) {
+        return Err(format!("speed factor must be in (0, 1000], got {}", factor));
+    }
+    Ok(factor)
+}
+
+impl fmt::Display for ClockMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockMode::RealTime => write!(f, "real time"),
+            ClockMode::Accelerated(factor) => write!(f, "{}x", factor),
+            ClockMode::Stepped => write!(f, "step by step"),
+        }
+    }
+}
+
+/// the supervisor's clock, every universe steps when it ticks
+pub struct SimulationClock {
+    mode: ClockMode,
+    tick: u64,
+    ticker: Option<Interval>,
+}
+
+impl SimulationClock {
+    pub fn new(mode: ClockMode) -> Self {
+        SimulationClock {
+            mode,
+            tick: 0,
+            ticker: Self::ticker_for(mode),
+        }
+    }
+
+    fn ticker_for(mode: ClockMode) -> Option<Interval> {
+        mode.period().map(|period| {
+            let mut ticker = interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            ticker
+        })
+    }
+
+    pub fn mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ClockMode) {
+        self.mode = mode;
+        self.ticker = Self::ticker_for(mode);
+    }
+
+    /// ticks elapsed since the simulation started
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// resolves when the next timed tick is due, never in step mode
+    pub async fn wait_next_tick(&mut self) {
+        match &mut self.ticker {
+            Some(ticker) => { ticker.tick().await; }
+            None => std::future::pending::<()>().await,
+        }
+    }
+
+    /// jumps to a tick, for restoring a saved simulation
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+        logging::set_current_tick(tick);
+    }
+
+    pub fn advance(&mut self) -> u64 {
+        self.set_tick(self.tick + 1);
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[test]
+    fn clock_and_replay_speeds_parse_alike() {
+        assert_eq!(parse_speed_factor("4x"), Ok(4.0));
+        assert_eq!(parse_speed_factor("0.5"), Ok(0.5));
+        assert!(parse_speed_factor("0x").is_err());
+        assert!(parse_speed_factor("1001x").is_err());
+        assert!(parse_speed_factor("fast").is_err());
+
+        assert_eq!("10X".parse::<ClockMode>(), Ok(ClockMode::Accelerated(10.0)));
+        assert_eq!("real".parse::<ClockMode>(), Ok(ClockMode::RealTime));
+        assert_eq!("step".parse::<ClockMode>(), Ok(ClockMode::Stepped));
+        assert!("0x".parse::<ClockMode>().is_err());
+    }
+
+    #[tokio::test]
+    async fn stepped_clock_only_moves_when_told() {
+        let mut clock = SimulationClock::new(ClockMode::Stepped);
+        assert_eq!(clock.tick(), 0);
+
+        // no timer of its own
+        assert!(timeout(TICK_PERIOD, clock.wait_next_tick()).await.is_err());
+        assert_eq!(clock.tick(), 0);
+
+        assert_eq!(clock.advance(), 1);
+        assert_eq!(clock.advance(), 2);
+        assert_eq!(clock.tick(), 2);
+
+        // restored runs go on from the saved tick
+        clock.set_tick(41);
+        assert_eq!(clock.advance(), 42);
+
+        // switching to a timed mode keeps the tick, and the timer starts ticking
+        clock.set_mode(ClockMode::Accelerated(10.0));
+        assert!(timeout(TICK_PERIOD, clock.wait_next_tick()).await.is_ok());
+        assert_eq!(clock.tick(), 42);
+    }
+}
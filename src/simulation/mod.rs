@@ -0,0 +1,5 @@
+﻿pub mod seed;
+pub mod clock;
+
+pub use seed::SimulationSeed;
+pub use clock::{parse_speed_factor, ClockMode, SimulationClock};
@@ -0,0 +1,32 @@
+﻿use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::universe::UniverseId;
+
+/// the one seed every random roll of a run comes from.
+/// same seed + same commands = same battle.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationSeed(pub u64);
+
+impl SimulationSeed {
+    pub fn random() -> Self {
+        SimulationSeed(rand::random())
+    }
+
+    /// rng of a single universe, its rolls don't depend on what other universes roll
+    pub fn universe_rng(&self, id: UniverseId) -> StdRng {
+        StdRng::seed_from_u64(mix(self.0, id as u64 + 1))
+    }
+
+    /// rng of the supervisor (relationship rolls), stream 0 is never a universe id
+    pub fn supervisor_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(mix(self.0, 0))
+    }
+}
+
+/// splitmix64 finalizer, spreads close seeds / ids far apart
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
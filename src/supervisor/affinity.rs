@@ -0,0 +1,181 @@
+﻿use crate::supervisor::error::{SupervisorError, UniverseLookupError};
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{Relationship, UniverseCommand, UniverseId, AFFINITY_RANGE};
+
+/// how much a pair's affinity moves when one of them hits or heals someone the other cares about
+const BYSTANDER_DRIFT: i32 = 2;
+
+/// relationships go both ways, a pair is stored once
+pub(crate) fn relationship_key(first: UniverseId, second: UniverseId) -> (UniverseId, UniverseId) {
+    (first.min(second), first.max(second))
+}
+
+impl SupervisorHandle {
+    pub(crate) fn affinity_between(&self, first: UniverseId, second: UniverseId) -> Option<i32> {
+        self.affinities.get(&relationship_key(first, second)).copied()
+    }
+
+    pub(crate) fn relationship_between(&self, first: UniverseId, second: UniverseId) -> Option<Relationship> {
+        self.affinity_between(first, second).map(Relationship::from_affinity)
+    }
+
+    /// sets the affinity of a pair. when that changes what they are to each other,
+    /// both universes are told and the change is announced.
+    /// the pair only changes here once both universes got the news, so neither side disagrees.
+    pub(crate) async fn set_affinity(&mut self, first: UniverseId, second: UniverseId, affinity: i32) -> Result<(), SupervisorError> {
+        let first_tx = self.get_universe_handle_by_id(first)?.commander_tx.clone();
+        let second_tx = self.get_universe_handle_by_id(second)?.commander_tx.clone();
+
+        let key = relationship_key(first, second);
+        let affinity = affinity.clamp(*AFFINITY_RANGE.start(), *AFFINITY_RANGE.end());
+        let before = self.affinities.get(&key).copied().map(Relationship::from_affinity);
+        let relationship = Relationship::from_affinity(affinity);
+        if before == Some(relationship) {
+            self.affinities.insert(key, affinity);
+            return Ok(());
+        }
+
+        first_tx.send(UniverseCommand::SetRelationship(second, relationship)).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(first))?;
+        if second_tx.send(UniverseCommand::SetRelationship(first, relationship)).await.is_err() {
+            // take it back from the first one, the pair stays as it was
+            let previous = before.unwrap_or(Relationship::Neutral);
+            let _ = first_tx.send(UniverseCommand::SetRelationship(second, previous)).await;
+            return Err(UniverseLookupError::UniverseUnresponsive(second).into());
+        }
+        self.affinities.insert(key, affinity);
+
+        Log::relationship_announcement(
+            self.get_universe_ref_by_id(&first),
+            self.get_universe_ref_by_id(&second),
+            relationship,
+            affinity,
+        );
+        Ok(())
+    }
+
+    /// hitting someone sours the pair, and how their friends and foes see the attacker
+    pub(crate) async fn drift_after_attack(&mut self, source: UniverseId, target: UniverseId, damage: i32) {
+        self.drift(source, target, -(damage / 2)).await
+    }
+
+    /// healing someone warms the pair, and how their friends and foes see the healer
+    pub(crate) async fn drift_after_heal(&mut self, source: UniverseId, target: UniverseId, amount: i32) {
+        self.drift(source, target, amount / 2).await
+    }
+
+    /// pairs bound by their factions never drift.
+    /// a pair that can't follow keeps its affinity, the others drift all the same.
+    async fn drift(&mut self, source: UniverseId, target: UniverseId, change: i32) {
+        if let Some(affinity) = self.affinity_between(source, target)
+            && !self.is_faction_bound(source, target) {
+            self.drift_pair(source, target, affinity + change).await;
+        }
+
+        // the target's friends side with it, its foes the other way round
+        let mut bystanders: Vec<UniverseId> = self.existing_universes.keys().copied()
+            .filter(|&id| id != source && id != target)
+            .collect();
+        bystanders.sort();
+
+        for bystander in bystanders {
+            let (Some(towards_target), Some(towards_source)) =
+                (self.relationship_between(bystander, target), self.affinity_between(bystander, source)) else {
+                continue;
+            };
+
+            let side = if towards_target.is_friendly() {
+                change.signum()
+            } else if towards_target.is_hostile() {
+                -change.signum()
+            } else {
+                0
+            };
+            if side != 0 && !self.is_faction_bound(bystander, source) {
+                self.drift_pair(bystander, source, towards_source + side * BYSTANDER_DRIFT).await;
+            }
+        }
+    }
+
+    async fn drift_pair(&mut self, first: UniverseId, second: UniverseId, affinity: i32) {
+        if let Err(e) = self.set_affinity(first, second, affinity).await {
+            Log::info(format!(
+                "Affinity between {} and {} not updated: {}",
+                self.get_universe_name_by_id(&first), self.get_universe_name_by_id(&second), e,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+    use crate::simulation::{ClockMode, SimulationSeed};
+    use crate::supervisor::perception::PerceptionConfig;
+    use crate::supervisor::supervisor_task::tests::RUNNING;
+    use crate::universe::UniverseOptions;
+    use super::*;
+
+    /// universes by name, every pair neutral to start with
+    async fn supervisor(names: &[&str]) -> (SupervisorHandle, Vec<UniverseId>) {
+        let mut supervisor = SupervisorHandle::new(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        let mut ids = Vec::new();
+        for &name in names {
+            supervisor.add_new_universe(name.to_owned(), UniverseOptions::default()).await;
+            ids.push(supervisor.get_universe_handle_by_name(name).unwrap().handle_id);
+        }
+        supervisor.affinities.clear();
+        (supervisor, ids)
+    }
+
+    #[tokio::test]
+    async fn attacks_and_heals_drift_the_pair_and_the_bystanders() {
+        let _running = RUNNING.lock().await;
+        let (mut supervisor, ids) = supervisor(&["source", "target", "friend", "foe", "stranger"]).await;
+        let [source, target, friend, foe, stranger] = ids[..] else { unreachable!() };
+        for (first, second, affinity) in [
+            (source, target, -30), (friend, target, 80), (foe, target, -80), (stranger, target, 0),
+            (friend, source, 0), (foe, source, 0), (stranger, source, 0),
+        ] {
+            supervisor.affinities.insert(relationship_key(first, second), affinity);
+        }
+
+        supervisor.drift_after_attack(source, target, 10).await;
+        assert_eq!(supervisor.affinity_between(source, target), Some(-35));
+        assert_eq!(supervisor.affinity_between(friend, source), Some(-BYSTANDER_DRIFT));
+        assert_eq!(supervisor.affinity_between(foe, source), Some(BYSTANDER_DRIFT));
+        assert_eq!(supervisor.affinity_between(stranger, source), Some(0));
+
+        supervisor.drift_after_heal(source, target, 10).await;
+        assert_eq!(supervisor.affinity_between(source, target), Some(-30));
+        assert_eq!(supervisor.affinity_between(friend, source), Some(0));
+        assert_eq!(supervisor.affinity_between(foe, source), Some(0));
+
+        supervisor.shut_down_all().await;
+    }
+
+    #[tokio::test]
+    async fn unresponsive_bystander_does_not_stop_the_others() {
+        let _running = RUNNING.lock().await;
+        let (mut supervisor, ids) = supervisor(&["source", "target", "gone", "friend"]).await;
+        let [source, target, gone, friend] = ids[..] else { unreachable!() };
+        for (first, second, affinity) in [
+            (gone, target, 80), (friend, target, 80),
+            (gone, source, -18), (friend, source, -18),
+        ] {
+            supervisor.affinities.insert(relationship_key(first, second), affinity);
+        }
+
+        // its task stops once nobody can send to it anymore
+        let (closed_tx, _) = mpsc::channel(1);
+        supervisor.existing_universes.get_mut(&gone).unwrap().commander_tx = closed_tx;
+
+        // both would turn rivals, only the one that can be told does
+        supervisor.drift_after_attack(source, target, 10).await;
+        assert_eq!(supervisor.affinity_between(gone, source), Some(-18));
+        assert_eq!(supervisor.affinity_between(friend, source), Some(-20));
+
+        supervisor.shut_down_all().await;
+    }
+}
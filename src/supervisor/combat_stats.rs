@@ -0,0 +1,84 @@
+﻿use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+use crate::logging::UniverseRef;
+use crate::supervisor::supervisor::SupervisorHandle;
+
+/// what a universe did over the whole run, kept by the `MatchTracker` next to the match scores
+#[derive(Debug, Clone)]
+pub struct CombatStats {
+    pub universe: UniverseRef,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub heals_given: i32,
+    pub heals_received: i32,
+    pub kills: u32,
+    pub joined_at: u64,
+    pub fell_at: Option<u64>,
+    pub uptime: u64, // ticks alive, filled in when the stats are handed out
+}
+
+/// what the leaderboard is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsColumn {
+    Name,
+    #[default]
+    Dealt,
+    Taken,
+    Heals,
+    Kills,
+    Uptime,
+}
+
+impl StatsColumn {
+    pub const ALL: [StatsColumn; 6] = [
+        StatsColumn::Name,
+        StatsColumn::Dealt,
+        StatsColumn::Taken,
+        StatsColumn::Heals,
+        StatsColumn::Kills,
+        StatsColumn::Uptime,
+    ];
+
+    /// names sort a to z, numbers highest first. the living come before the fallen on ties.
+    pub fn sort(&self, stats: &mut [CombatStats]) {
+        match self {
+            StatsColumn::Name => stats.sort_by(|a, b| a.universe.name.cmp(&b.universe.name)),
+            StatsColumn::Dealt => stats.sort_by_key(|s| (Reverse(s.damage_dealt), s.fell_at.is_some())),
+            StatsColumn::Taken => stats.sort_by_key(|s| (Reverse(s.damage_taken), s.fell_at.is_some())),
+            StatsColumn::Heals => stats.sort_by_key(|s| (Reverse(s.heals_given), s.fell_at.is_some())),
+            StatsColumn::Kills => stats.sort_by_key(|s| (Reverse(s.kills), s.fell_at.is_some())),
+            StatsColumn::Uptime => stats.sort_by_key(|s| (Reverse(s.uptime), s.fell_at.is_some())),
+        }
+    }
+}
+
+impl FromStr for StatsColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StatsColumn::ALL.into_iter()
+            .find(|column| column.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown column '{}', expected name, dealt, taken, heals, kills or uptime", s))
+    }
+}
+
+impl fmt::Display for StatsColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StatsColumn::Name => "name",
+            StatsColumn::Dealt => "dealt",
+            StatsColumn::Taken => "taken",
+            StatsColumn::Heals => "heals",
+            StatsColumn::Kills => "kills",
+            StatsColumn::Uptime => "uptime",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl SupervisorHandle {
+    pub fn combat_stats(&self) -> Vec<CombatStats> {
+        self.match_tracker.stats(self.clock.tick())
+    }
+}
@@ -0,0 +1,46 @@
+﻿use tokio::sync::oneshot;
+use crate::supervisor::error::{SupervisorError, UniverseLookupError};
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{DiplomaticAction, UniverseCommand, UniverseId};
+
+impl SupervisorHandle {
+    /// a universe tries to change what it is to another. war is declared on the spot,
+    /// alliances and peace go through only if the target accepts.
+    pub(crate) async fn diplomacy_intent(
+        &mut self,
+        source_id: UniverseId,
+        target_id: UniverseId,
+        action: DiplomaticAction) -> Result<(), SupervisorError> {
+        let source = self.get_universe_handle_by_id(source_id)?.universe_ref();
+        let target_tx = self.get_universe_handle_by_id(target_id)?.commander_tx.clone();
+
+        let accepted = match action {
+            DiplomaticAction::DeclareWar => true,
+            _ => {
+                let (reply_tx, reply_rx) = oneshot::channel();
+                target_tx.send(UniverseCommand::Consider { from: source_id, action, reply: reply_tx }).await
+                    .map_err(|_| UniverseLookupError::UniverseUnresponsive(target_id))?;
+                reply_rx.await
+                    .map_err(|_| UniverseLookupError::UniverseUnresponsive(target_id))?
+            }
+        };
+
+        Log::diplomacy(source, self.get_universe_ref_by_id(&target_id), action, accepted);
+
+        // both sides hear of the outcome together
+        if accepted {
+            self.set_affinity(source_id, target_id, action.affinity()).await?;
+        }
+        Ok(())
+    }
+
+    /// the user acting on behalf of a universe, checked like any of its own intents
+    pub async fn send_diplomacy(&mut self, source_name: &str, target_name: &str, action: DiplomaticAction) -> Result<(), UniverseLookupError> {
+        let source_id = self.get_universe_handle_by_name(source_name)?.handle_id;
+        let target_id = self.get_universe_handle_by_name(target_name)?.handle_id;
+
+        self.process_intent(source_id, action.intent(target_id)).await;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 ﻿use std::fmt;
+use crate::supervisor::rules::RuleViolation;
 use crate::universe::UniverseId;
 
 /// Errors that can occur when looking up universes
@@ -6,6 +7,7 @@ use crate::universe::UniverseId;
 pub enum UniverseLookupError {
     IdNotFoundForName(String),
     UniverseNotFoundForId(UniverseId),
+    UniverseUnresponsive(UniverseId),
 }
 
 impl fmt::Display for UniverseLookupError {
@@ -17,8 +19,78 @@ impl fmt::Display for UniverseLookupError {
             UniverseLookupError::UniverseNotFoundForId(id) => {
                 write!(f, "Universe with ID '{}' not found", id)
             }
+            UniverseLookupError::UniverseUnresponsive(id) => {
+                write!(f, "Universe with ID '{}' did not respond", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UniverseLookupError {}
+
+/// Errors of the supervisor applying an intent or a relationship.
+/// none of them is fatal, the intent is dropped and the reason logged
+#[derive(Debug, Clone)]
+pub enum SupervisorError {
+    Lookup(UniverseLookupError),
+    Rejected(RuleViolation),
+}
+
+impl fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupervisorError::Lookup(e) => write!(f, "{}", e),
+            SupervisorError::Rejected(violation) => write!(f, "{}", violation),
+        }
+    }
+}
+
+impl std::error::Error for SupervisorError {}
+
+impl From<UniverseLookupError> for SupervisorError {
+    fn from(e: UniverseLookupError) -> Self {
+        SupervisorError::Lookup(e)
+    }
+}
+
+impl From<RuleViolation> for SupervisorError {
+    fn from(violation: RuleViolation) -> Self {
+        SupervisorError::Rejected(violation)
+    }
+}
+
+/// Errors that can occur when saving or loading a multiverse snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "I/O error: {}", e),
+            SnapshotError::Format(e) => write!(f, "Malformed snapshot: {}", e),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "Snapshot version {} is not supported", version)
+            }
+            SnapshotError::Invalid(reason) => write!(f, "Invalid snapshot: {}", reason),
         }
     }
 }
 
-impl std::error::Error for UniverseLookupError {}
\ No newline at end of file
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Format(e)
+    }
+}
\ No newline at end of file
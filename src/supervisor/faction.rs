@@ -0,0 +1,122 @@
+﻿use std::collections::{BTreeMap, BTreeSet};
+use rgb::RGB8;
+use crate::logging::FactionRef;
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{UniverseHandle, UniverseId, AFFINITY_RANGE};
+
+/// colours for the usual team names, anything else is picked from these by name
+const FACTION_COLORS: [(&str, RGB8); 8] = [
+    ("red", RGB8::new(230, 60, 60)),
+    ("blue", RGB8::new(70, 120, 240)),
+    ("green", RGB8::new(70, 200, 90)),
+    ("yellow", RGB8::new(230, 210, 60)),
+    ("purple", RGB8::new(170, 90, 220)),
+    ("orange", RGB8::new(240, 150, 40)),
+    ("cyan", RGB8::new(60, 210, 220)),
+    ("pink", RGB8::new(240, 120, 180)),
+];
+
+/// a faction as it appears in events, its colour follows from its name
+pub fn faction_ref(name: &str) -> FactionRef {
+    let color = FACTION_COLORS.iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .map(|(_, color)| *color)
+        .unwrap_or_else(|| {
+            let hash = name.bytes().fold(0usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize));
+            FACTION_COLORS[hash % FACTION_COLORS.len()].1
+        });
+
+    FactionRef { name: name.to_owned(), color }
+}
+
+/// the faction that won once `fallen` lost its last member: the only one with members left
+fn last_faction_standing<'a>(fallen: &str, standing: &BTreeSet<&'a str>) -> Option<&'a str> {
+    if standing.contains(fallen) || standing.len() != 1 {
+        return None;
+    }
+    standing.first().copied()
+}
+
+impl SupervisorHandle {
+    fn faction_of(&self, id: UniverseId) -> Option<&str> {
+        self.existing_universes.get(&id)?.faction.as_ref().map(|f| f.name.as_str())
+    }
+
+    /// what two universes of factions are to each other: brothers in the same one, enemies otherwise.
+    /// `None` when either one fights for itself.
+    pub(crate) fn faction_affinity(&self, first: UniverseId, second: UniverseId) -> Option<i32> {
+        match (self.faction_of(first)?, self.faction_of(second)?) {
+            (a, b) if a == b => Some(*AFFINITY_RANGE.end()),
+            _ => Some(*AFFINITY_RANGE.start()),
+        }
+    }
+
+    /// their factions decide what they are, nothing they do changes it
+    pub(crate) fn is_faction_bound(&self, first: UniverseId, second: UniverseId) -> bool {
+        self.faction_affinity(first, second).is_some()
+    }
+
+    /// every faction with a living member, and its members
+    pub fn factions(&self) -> BTreeMap<String, Vec<String>> {
+        let mut factions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for handle in self.existing_universes.values() {
+            if let Some(faction) = &handle.faction {
+                factions.entry(faction.name.clone()).or_default().push(handle.own_name.clone());
+            }
+        }
+        for members in factions.values_mut() {
+            members.sort();
+        }
+        factions
+    }
+
+    /// called once `fallen` is gone. if that wiped out its faction and a single one is left, it won.
+    pub(crate) fn check_faction_victory(&self, fallen: &UniverseHandle) {
+        let Some(fallen_faction) = &fallen.faction else {
+            return;
+        };
+
+        let mut ids: Vec<UniverseId> = self.existing_universes.keys().copied().collect();
+        ids.sort();
+        let standing: BTreeSet<&str> = ids.iter().filter_map(|&id| self.faction_of(id)).collect();
+
+        let Some(winner) = last_faction_standing(&fallen_faction.name, &standing) else {
+            return;
+        };
+
+        let survivors = ids.iter()
+            .filter(|&&id| self.faction_of(id) == Some(winner))
+            .map(|id| self.get_universe_ref_by_id(id))
+            .collect();
+        Log::faction_victory(faction_ref(winner), survivors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_faction_left_wins() {
+        let standing = BTreeSet::from(["red"]);
+        assert_eq!(last_faction_standing("blue", &standing), Some("red"));
+    }
+
+    #[test]
+    fn no_winner_while_several_factions_stand() {
+        let standing = BTreeSet::from(["green", "red"]);
+        assert_eq!(last_faction_standing("blue", &standing), None);
+    }
+
+    #[test]
+    fn no_winner_while_the_fallen_faction_has_members() {
+        let standing = BTreeSet::from(["blue"]);
+        assert_eq!(last_faction_standing("blue", &standing), None);
+    }
+
+    #[test]
+    fn no_winner_once_every_faction_is_gone() {
+        assert_eq!(last_faction_standing("blue", &BTreeSet::new()), None);
+    }
+}
@@ -0,0 +1,99 @@
+﻿use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{UniverseId, UniverseIntent};
+
+/// how the supervisor empties the universes' intent queues
+#[derive(Debug, Clone, Copy)]
+pub struct DrainPolicy {
+    /// max intents taken from a single universe in one processing cycle
+    pub budget_per_universe: usize,
+}
+
+impl Default for DrainPolicy {
+    fn default() -> Self {
+        DrainPolicy { budget_per_universe: 16 }
+    }
+}
+
+/// intent queue stats of a single universe
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueMetrics {
+    pub depth: usize,      // intents waiting right now
+    pub peak_depth: usize, // highest depth seen at the start of a cycle
+    pub processed: u64,    // intents processed since creation
+}
+
+impl SupervisorHandle {
+    /// runs one processing cycle: takes intents round-robin, one per universe per pass,
+    /// until every queue is empty or has used up its budget.
+    /// the universe that goes first rotates between cycles, so no one is always first.
+    pub(crate) async fn drain_intents(&mut self) {
+        let batch = self.collect_intent_cycle();
+
+        for (source_id, intent) in batch {
+            // an earlier intent in this batch may have collapsed the source
+            if !self.existing_universes.contains_key(&source_id) {
+                continue;
+            }
+            self.process_intent(source_id, intent).await;
+        }
+    }
+
+    fn collect_intent_cycle(&mut self) -> Vec<(UniverseId, UniverseIntent)> {
+        let mut queues: Vec<&mut (UniverseId, _)> = self.intents.iter_mut().collect();
+        if queues.is_empty() {
+            return Vec::new();
+        }
+        queues.sort_by_key(|(id, _)| *id);
+        let first = self.drain_cursor % queues.len();
+        queues.rotate_left(first);
+        self.drain_cursor = self.drain_cursor.wrapping_add(1);
+
+        // remember how deep every queue was before draining it
+        for (id, stream) in queues.iter_mut().map(|queue| &mut **queue) {
+            let metrics = self.queue_metrics.entry(*id).or_default();
+            metrics.peak_depth = metrics.peak_depth.max(stream.as_mut().len());
+        }
+
+        let mut batch = Vec::new();
+        for _pass in 0..self.drain_policy.budget_per_universe {
+            let mut took_any = false;
+
+            for (id, stream) in queues.iter_mut().map(|queue| &mut **queue) {
+                if let Ok(intent) = stream.as_mut().try_recv() {
+                    batch.push((*id, intent));
+                    self.queue_metrics.entry(*id).or_default().processed += 1;
+                    took_any = true;
+                }
+            }
+
+            if !took_any {
+                break;
+            }
+        }
+
+        batch
+    }
+
+    /// counts an intent that was taken outside of a drain cycle
+    pub(crate) fn record_processed_intent(&mut self, source_id: UniverseId) {
+        self.queue_metrics.entry(source_id).or_default().processed += 1;
+    }
+
+    /// current queue stats of every universe, by name
+    pub fn get_queue_metrics(&mut self) -> Vec<(String, QueueMetrics)> {
+        let mut depths: Vec<(UniverseId, usize)> = self.intents.iter_mut()
+            .map(|(id, stream)| (*id, stream.as_mut().len()))
+            .collect();
+        depths.sort();
+
+        depths.into_iter()
+            .map(|(id, depth)| {
+                let metrics = QueueMetrics {
+                    depth,
+                    ..self.queue_metrics.get(&id).copied().unwrap_or_default()
+                };
+                (self.get_universe_name_by_id(&id), metrics)
+            })
+            .collect()
+    }
+}
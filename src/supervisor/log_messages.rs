@@ -1,87 +1,71 @@
-﻿use ratatui::text::Span;
-use ratatui::style::{Color, Style};
-use rgb::RGB8;
-use crate::logging::log;
+﻿use std::time::Duration;
+use crate::logging::{log, FactionRef, SimulationEvent, UniverseRef, UniverseScore};
+use crate::universe::{DiplomaticAction, Relationship, UniverseStatus};
 
+/// shorthands for putting events on the log bus
 pub struct Log;
 
 impl Log {
-    fn color(rgb: RGB8) -> Color {
-        Color::Rgb(rgb.r, rgb.g, rgb.b)
+    pub fn info(msg: impl Into<String>) {
+        log(SimulationEvent::Info { message: msg.into() });
     }
 
-    pub fn info(msg: impl Into<String>) {
-        let msg = msg.into();
-        let spans = vec![
-            Span::styled("> ".to_owned(), Style::default().fg(Color::Cyan)),
-            Span::raw(msg),
-        ];
-        log(spans);
+    pub fn created(universe: UniverseRef) {
+        log(SimulationEvent::Created { universe });
+    }
+
+    pub fn relationship_announcement(first: UniverseRef, second: UniverseRef, relationship: Relationship, affinity: i32) {
+        log(SimulationEvent::RelationshipSet { first, second, relationship, affinity });
     }
 
-    pub fn created(name: &str, color: RGB8) {
-        let spans = vec![
-            Span::styled("> Created universe ".to_owned(), Style::default().fg(Color::Cyan)),
-            Span::styled(name.to_owned(), Style::default().fg(Self::color(color))),
-        ];
-        log(spans);
+    pub fn attack(source: UniverseRef, target: UniverseRef, damage: i32) {
+        log(SimulationEvent::Attack { source, target, damage });
     }
 
-    pub fn relationship_announcement(
-        name1: &str, color1: RGB8,
-        name2: &str, color2: RGB8,
-        relation: &str,
-        subtitle: &str,
-    ) {
+    pub fn heal(source: UniverseRef, target: UniverseRef, amount: i32) {
+        log(SimulationEvent::Heal { source, target, amount });
+    }
+
+    pub fn collapsed(universe: UniverseRef) {
+        log(SimulationEvent::Collapsed { universe });
+    }
+
+    pub fn pong(source: UniverseRef, target: UniverseRef, round_trip: Duration) {
+        log(SimulationEvent::Pong { source, target, round_trip_us: round_trip.as_micros() as u64 });
+    }
+
+    /// war declared, or a proposal answered
+    pub fn diplomacy(source: UniverseRef, target: UniverseRef, action: DiplomaticAction, accepted: bool) {
+        log(SimulationEvent::Diplomacy { source, target, action, accepted });
+    }
+
+    pub fn faction_victory(faction: FactionRef, survivors: Vec<UniverseRef>) {
+        log(SimulationEvent::FactionVictory { faction, survivors });
+    }
 
-        let spans = vec![
-            Span::styled("Relationship: ".to_owned(), Style::default().fg(Color::Cyan)),
-            Span::styled(name1.to_owned(), Style::default().fg(Self::color(color1))),
-            Span::raw(" and ".to_owned()),
-            Span::styled(name2.to_owned(), Style::default().fg(Self::color(color2))),
-            Span::styled(format!(" are {relation} ").to_owned(), Style::default().fg(Color::Yellow)),
-            Span::styled(format!("{subtitle}").to_owned(), Style::default().fg(Color::DarkGray)),
-        ];
-        log(spans);
+    pub fn status(universe: UniverseRef, hp: i32, status: UniverseStatus) {
+        log(SimulationEvent::Status { universe, hp, status });
     }
 
-    pub fn attack(source: &str, source_color: RGB8, target: &str, target_color: RGB8, dmg: i32) {
-        let spans = vec![
-            Span::raw("[".to_owned()),
-            Span::styled(source.to_owned(), Style::default().fg(Self::color(source_color))),
-            Span::raw("] → [".to_owned()),
-            Span::styled(target.to_owned(), Style::default().fg(Self::color(target_color))),
-            Span::styled(format!("] −{dmg} HP"), Style::default().fg(Color::Red)),
-        ];
-        log(spans);
+    /// a loaded snapshot is about to replace every universe
+    pub fn reset(tick: u64) {
+        log(SimulationEvent::Reset { tick });
     }
 
-    pub fn heal(source: &str, source_color: RGB8, target: &str, target_color: RGB8, amount: i32) {
-        let spans = vec![
-            Span::raw("[".to_owned()),
-            Span::styled(source.to_owned(), Style::default().fg(Self::color(source_color))),
-            Span::raw("] healed [".to_owned()),
-            Span::styled(target.to_owned(), Style::default().fg(Self::color(target_color))),
-            Span::styled(format!("] +{amount} HP"), Style::default().fg(Color::Green)),
-        ];
-        log(spans);
+    pub fn match_over(winners: Vec<UniverseRef>, scoreboard: Vec<UniverseScore>) {
+        log(SimulationEvent::MatchOver { winners, scoreboard });
     }
 
-    pub fn collapsed(name: &str, color: RGB8) {
-        let spans = vec![
-            Span::styled("☠ ".to_owned(), Style::default().fg(Color::Red)),
-            Span::styled(name.to_owned(), Style::default().fg(Self::color(color))),
-            Span::styled(" has COLLAPSED".to_owned(), Style::default().fg(Color::DarkGray)),
-        ];
-        log(spans);
+    /// the supervisor refused an intent
+    pub fn rejected(source: UniverseRef, intent: String, reason: String) {
+        log(SimulationEvent::Rejected { source, intent, reason });
     }
 
-    pub fn user_action(actor: &str, action: &str, target: &str, color: RGB8) {
-        let spans = vec![
-            Span::styled("> ".to_owned(), Style::default().fg(Color::Cyan)),
-            Span::styled(actor.to_owned(), Style::default().fg(Self::color(color))),
-            Span::raw(format!(" {action} {target}")),
-        ];
-        log(spans);
+    pub fn user_action(actor: &str, action: &str, target: UniverseRef) {
+        log(SimulationEvent::UserAction {
+            actor: actor.to_owned(),
+            action: action.to_owned(),
+            target,
+        });
     }
-}
\ No newline at end of file
+}
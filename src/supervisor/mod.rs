@@ -1,4 +1,18 @@
 ﻿pub mod supervisor;
 pub(crate) mod user_supervisor;
 pub mod log_messages;
+pub mod universe_report;
+pub mod supervisor_client;
+mod supervisor_command;
+mod supervisor_task;
+pub mod intent_queue;
+pub mod snapshot;
+pub mod perception;
+pub mod rules;
+pub mod affinity;
+pub mod diplomacy;
+pub mod faction;
+pub mod scoreboard;
+pub mod combat_stats;
+pub mod overview;
 mod error;
@@ -0,0 +1,116 @@
+﻿use std::collections::{BTreeMap, VecDeque};
+use crate::logging::{LoggedEvent, SimulationEvent, UniverseRef};
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{UniverseId, UniverseStatus};
+
+/// how many ticks of hp history are kept for the sparklines
+const HP_HISTORY: usize = 60;
+
+/// one universe as the overview panel shows it
+#[derive(Debug, Clone)]
+pub struct UniverseOverview {
+    pub universe: UniverseRef,
+    pub hp: i32,
+    pub status: UniverseStatus,
+    pub hp_history: VecDeque<u64>, // one per tick, oldest first
+}
+
+/// hp and status of every living universe. the supervisor keeps it from the reports
+/// they answer each tick with and logs every change, front-ends rebuild it from those events.
+#[derive(Debug, Default)]
+pub struct StatusBoard {
+    entries: BTreeMap<UniverseId, UniverseOverview>,
+    sampled_at: u64, // the tick the hp history goes up to
+}
+
+impl StatusBoard {
+    pub fn joined(&mut self, universe: UniverseRef, hp: i32, status: UniverseStatus) {
+        self.entries.insert(universe.id, UniverseOverview {
+            universe,
+            hp,
+            status,
+            hp_history: VecDeque::from([hp.max(0) as u64]),
+        });
+    }
+
+    /// returns the universe when its hp or status changed. without a new hp it keeps the old one.
+    pub fn update(&mut self, id: UniverseId, hp: Option<i32>, status: UniverseStatus) -> Option<&UniverseOverview> {
+        let entry = self.entries.get_mut(&id)?;
+        let hp = hp.unwrap_or(entry.hp);
+        if entry.hp == hp && entry.status == status {
+            return None;
+        }
+        entry.hp = hp;
+        entry.status = status;
+        Some(entry)
+    }
+
+    pub fn remove(&mut self, id: UniverseId) {
+        self.entries.remove(&id);
+    }
+
+    pub fn clear(&mut self) {
+        *self = StatusBoard::default();
+    }
+
+    /// adds the hp every universe had to the history, once for each tick since the last call
+    pub fn sample_until(&mut self, tick: u64) {
+        let ticks = tick.saturating_sub(self.sampled_at).min(HP_HISTORY as u64);
+        for entry in self.entries.values_mut() {
+            for _ in 0..ticks {
+                entry.hp_history.push_back(entry.hp.max(0) as u64);
+            }
+            while entry.hp_history.len() > HP_HISTORY {
+                entry.hp_history.pop_front();
+            }
+        }
+        self.sampled_at = self.sampled_at.max(tick);
+    }
+
+    /// follows a logged event, for front-ends that only see the log bus
+    pub fn observe(&mut self, entry: &LoggedEvent) {
+        // the ticks before this event are over
+        self.sample_until(entry.tick.saturating_sub(1));
+
+        match &entry.event {
+            SimulationEvent::Created { universe } => self.joined(universe.clone(), 100, UniverseStatus::Running),
+            SimulationEvent::Status { universe, hp, status } => {
+                self.update(universe.id, Some(*hp), *status);
+            }
+            SimulationEvent::Collapsed { universe } => self.remove(universe.id),
+            SimulationEvent::Rewound { tick } | SimulationEvent::Reset { tick } => {
+                self.clear();
+                self.sampled_at = *tick;
+            }
+            _ => {}
+        }
+    }
+
+    /// a universe that has hp left and is not on its way out
+    pub fn is_standing(&self, id: UniverseId) -> bool {
+        self.entries.get(&id).is_some_and(|entry| entry.hp > 0 && entry.status != UniverseStatus::Collapsing)
+    }
+
+    /// every standing universe in id order
+    pub fn standing(&self) -> Vec<UniverseRef> {
+        self.entries.iter()
+            .filter(|&(&id, _)| self.is_standing(id))
+            .map(|(_, entry)| entry.universe.clone())
+            .collect()
+    }
+
+    /// every living universe in id order
+    pub fn overview(&self) -> Vec<UniverseOverview> {
+        self.entries.values().cloned().collect()
+    }
+}
+
+impl SupervisorHandle {
+    /// keeps the status board up to date, and tells the log bus when a universe's hp or status changed
+    pub(crate) fn update_status(&mut self, id: UniverseId, hp: Option<i32>, status: UniverseStatus) {
+        if let Some(entry) = self.status_board.update(id, hp, status) {
+            Log::status(entry.universe.clone(), entry.hp, entry.status);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+﻿use std::collections::{BTreeMap, VecDeque};
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{NeighbourStatus, NeighbourView, UniverseCommand, UniverseEvent, UniverseId, WorldView};
+
+/// how often universes get to see each other, and how old what they see is
+#[derive(Debug, Clone, Copy)]
+pub struct PerceptionConfig {
+    pub interval: u64, // observe every this many ticks
+    pub fog: u64,      // deliver observations this many ticks late
+}
+
+impl Default for PerceptionConfig {
+    fn default() -> Self {
+        PerceptionConfig { interval: 4, fog: 0 }
+    }
+}
+
+/// observations waiting for the fog to let them through
+#[derive(Default)]
+pub(crate) struct Perception {
+    pending: VecDeque<Observation>,
+}
+
+impl Perception {
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+struct Observation {
+    tick: u64,
+    universes: BTreeMap<UniverseId, NeighbourView>,
+}
+
+impl SupervisorHandle {
+    /// called every tick: observe the multiverse when due, deliver what the fog lets through
+    pub(crate) async fn update_perception(&mut self) {
+        let tick = self.clock.tick();
+
+        if tick.is_multiple_of(self.perception_config.interval) {
+            let universes = self.query_snapshots().await.into_iter()
+                .map(|(id, state)| (id, NeighbourView {
+                    hp: state.hp,
+                    status: if state.executes { NeighbourStatus::Running } else { NeighbourStatus::Paused },
+                }))
+                .collect();
+            self.perception.pending.push_back(Observation { tick, universes });
+        }
+
+        let fog = self.perception_config.fog;
+        while self.perception.pending.front().is_some_and(|observation| observation.tick + fog <= tick) {
+            if let Some(observation) = self.perception.pending.pop_front() {
+                self.deliver(observation).await;
+            }
+        }
+    }
+
+    /// every universe sees all the others, not itself
+    async fn deliver(&self, observation: Observation) {
+        let mut ids: Vec<UniverseId> = self.existing_universes.keys().copied().collect();
+        ids.sort();
+
+        for id in ids {
+            let mut neighbours = observation.universes.clone();
+            neighbours.remove(&id);
+
+            let view = WorldView { tick: observation.tick, neighbours };
+            let _ = self.existing_universes[&id].commander_tx
+                .send(UniverseCommand::InjectEvent(UniverseEvent::WorldView(view))).await;
+        }
+    }
+}
@@ -0,0 +1,217 @@
+﻿use std::fmt;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{DiplomaticAction, UniverseId, UniverseIntent, MAX_STRENGTH};
+
+/// why the supervisor refused to apply an intent
+#[derive(Debug, Clone)]
+pub enum RuleViolation {
+    SourceGone,
+    SourcePaused,
+    TargetGone,
+    TargetIsSource,
+    NotEnemies,
+    NotBrothers,
+    OutOfBounds { value: i32 },
+    NotOwnDeath,
+    AlreadySettled(DiplomaticAction),
+    FactionBound,
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleViolation::SourceGone => write!(f, "the source no longer exists"),
+            RuleViolation::SourcePaused => write!(f, "the source is paused"),
+            RuleViolation::TargetGone => write!(f, "the target no longer exists"),
+            RuleViolation::TargetIsSource => write!(f, "a universe can't target itself"),
+            RuleViolation::NotEnemies => write!(f, "they are not enemies or rivals"),
+            RuleViolation::NotBrothers => write!(f, "they are not allies or brothers"),
+            RuleViolation::OutOfBounds { value } => {
+                write!(f, "{} is out of bounds (1 to {})", value, MAX_STRENGTH)
+            }
+            RuleViolation::NotOwnDeath => write!(f, "only a universe itself can report its collapse"),
+            RuleViolation::FactionBound => write!(f, "their factions decide that"),
+            RuleViolation::AlreadySettled(action) => match action {
+                DiplomaticAction::DeclareWar => write!(f, "they are already enemies"),
+                DiplomaticAction::Ally => write!(f, "they are already allies"),
+                DiplomaticAction::Peace => write!(f, "they are already at peace"),
+            },
+        }
+    }
+}
+
+impl SupervisorHandle {
+    /// the rules every intent has to pass before it is applied.
+    /// a paused universe can't do anything, it may only still die from user events.
+    pub(crate) fn check_intent(&self, source_id: UniverseId, intent: &UniverseIntent) -> Result<(), RuleViolation> {
+        if !self.existing_universes.contains_key(&source_id) {
+            return Err(RuleViolation::SourceGone);
+        }
+        if self.paused.contains(&source_id) && !matches!(intent, UniverseIntent::Dead { .. }) {
+            return Err(RuleViolation::SourcePaused);
+        }
+
+        match *intent {
+            UniverseIntent::Attack { target, damage } => {
+                self.check_action(source_id, target, damage)?;
+                if !self.relationship_between(source_id, target).is_some_and(|r| r.is_hostile()) {
+                    return Err(RuleViolation::NotEnemies);
+                }
+                Ok(())
+            }
+            UniverseIntent::Heal { target, amount } => {
+                self.check_action(source_id, target, amount)?;
+                if !self.relationship_between(source_id, target).is_some_and(|r| r.is_friendly()) {
+                    return Err(RuleViolation::NotBrothers);
+                }
+                Ok(())
+            }
+            UniverseIntent::Dead { target } if target != source_id => Err(RuleViolation::NotOwnDeath),
+            UniverseIntent::Dead { .. } => Ok(()),
+            UniverseIntent::Ping { target } | UniverseIntent::Pong { target, .. } => {
+                self.check_target(source_id, target)
+            }
+            UniverseIntent::DeclareWar { target } => self.check_diplomacy(source_id, target, DiplomaticAction::DeclareWar),
+            UniverseIntent::ProposeAlliance { target } => self.check_diplomacy(source_id, target, DiplomaticAction::Ally),
+            UniverseIntent::ProposePeace { target } => self.check_diplomacy(source_id, target, DiplomaticAction::Peace),
+        }
+    }
+
+    fn check_diplomacy(&self, source_id: UniverseId, target_id: UniverseId, action: DiplomaticAction) -> Result<(), RuleViolation> {
+        self.check_target(source_id, target_id)?;
+        if self.is_faction_bound(source_id, target_id) {
+            return Err(RuleViolation::FactionBound);
+        }
+
+        match self.relationship_between(source_id, target_id) {
+            Some(relationship) if action.is_settled(relationship) => Err(RuleViolation::AlreadySettled(action)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_action(&self, source_id: UniverseId, target_id: UniverseId, strength: i32) -> Result<(), RuleViolation> {
+        self.check_target(source_id, target_id)?;
+
+        if !(1..=MAX_STRENGTH).contains(&strength) {
+            return Err(RuleViolation::OutOfBounds { value: strength });
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, source_id: UniverseId, target_id: UniverseId) -> Result<(), RuleViolation> {
+        if target_id == source_id {
+            return Err(RuleViolation::TargetIsSource);
+        }
+        if !self.existing_universes.contains_key(&target_id) {
+            return Err(RuleViolation::TargetGone);
+        }
+        Ok(())
+    }
+
+    /// the intent in words, for the rejection log
+    pub(crate) fn describe_intent(&self, intent: &UniverseIntent) -> String {
+        match intent {
+            UniverseIntent::Attack { target, damage } => {
+                format!("attack on {} for {}", self.get_universe_name_by_id(target), damage)
+            }
+            UniverseIntent::Heal { target, amount } => {
+                format!("heal of {} for {}", self.get_universe_name_by_id(target), amount)
+            }
+            UniverseIntent::Dead { target } => format!("collapse of {}", self.get_universe_name_by_id(target)),
+            UniverseIntent::Ping { target } => format!("ping to {}", self.get_universe_name_by_id(target)),
+            UniverseIntent::Pong { target, .. } => format!("pong to {}", self.get_universe_name_by_id(target)),
+            UniverseIntent::DeclareWar { target } => format!("war declaration on {}", self.get_universe_name_by_id(target)),
+            UniverseIntent::ProposeAlliance { target } => format!("alliance proposal to {}", self.get_universe_name_by_id(target)),
+            UniverseIntent::ProposePeace { target } => format!("peace proposal to {}", self.get_universe_name_by_id(target)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{ClockMode, SimulationSeed};
+    use crate::supervisor::affinity::relationship_key;
+    use crate::supervisor::perception::PerceptionConfig;
+    use crate::supervisor::supervisor_task::tests::RUNNING;
+    use crate::universe::UniverseOptions;
+    use super::*;
+
+    /// foe and friend fight for themselves, red and blue for their factions
+    async fn supervisor() -> (SupervisorHandle, [UniverseId; 5]) {
+        let mut supervisor = SupervisorHandle::new(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        let mut ids = [0; 5];
+        for (i, (name, faction)) in [("me", None), ("foe", None), ("friend", None), ("red", Some("red")), ("blue", Some("blue"))].into_iter().enumerate() {
+            let options = UniverseOptions { faction: faction.map(str::to_owned), ..UniverseOptions::default() };
+            supervisor.add_new_universe(name.to_owned(), options).await;
+            ids[i] = supervisor.get_universe_handle_by_name(name).unwrap().handle_id;
+        }
+
+        // rolled on creation, set here so the tests don't depend on the seed
+        let [me, foe, friend, ..] = ids;
+        supervisor.affinities.insert(relationship_key(me, foe), -80);
+        supervisor.affinities.insert(relationship_key(me, friend), 80);
+        (supervisor, ids)
+    }
+
+    #[tokio::test]
+    async fn intents_within_the_rules_pass() {
+        let _running = RUNNING.lock().await;
+        let (mut supervisor, [me, foe, friend, ..]) = supervisor().await;
+
+        assert!(supervisor.check_intent(me, &UniverseIntent::Attack { target: foe, damage: MAX_STRENGTH }).is_ok());
+        assert!(supervisor.check_intent(me, &UniverseIntent::Heal { target: friend, amount: 1 }).is_ok());
+        assert!(supervisor.check_intent(me, &UniverseIntent::Ping { target: friend }).is_ok());
+        assert!(supervisor.check_intent(me, &UniverseIntent::ProposePeace { target: foe }).is_ok());
+        assert!(supervisor.check_intent(me, &UniverseIntent::Dead { target: me }).is_ok());
+
+        supervisor.shut_down_all().await;
+    }
+
+    #[tokio::test]
+    async fn every_violation_is_caught() {
+        let _running = RUNNING.lock().await;
+        let (mut supervisor, [me, foe, friend, red, blue]) = supervisor().await;
+        let gone = UniverseId::MAX;
+
+        let check = |supervisor: &SupervisorHandle, source, intent| supervisor.check_intent(source, &intent).unwrap_err();
+        assert!(matches!(check(&supervisor, gone, UniverseIntent::Ping { target: me }), RuleViolation::SourceGone));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Attack { target: gone, damage: 5 }), RuleViolation::TargetGone));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Heal { target: me, amount: 5 }), RuleViolation::TargetIsSource));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Attack { target: friend, damage: 5 }), RuleViolation::NotEnemies));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Heal { target: foe, amount: 5 }), RuleViolation::NotBrothers));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Attack { target: foe, damage: 0 }), RuleViolation::OutOfBounds { value: 0 }));
+        assert!(matches!(
+            check(&supervisor, me, UniverseIntent::Heal { target: friend, amount: MAX_STRENGTH + 1 }),
+            RuleViolation::OutOfBounds { .. },
+        ));
+        assert!(matches!(check(&supervisor, me, UniverseIntent::Dead { target: foe }), RuleViolation::NotOwnDeath));
+        assert!(matches!(
+            check(&supervisor, me, UniverseIntent::DeclareWar { target: foe }),
+            RuleViolation::AlreadySettled(DiplomaticAction::DeclareWar),
+        ));
+        assert!(matches!(check(&supervisor, red, UniverseIntent::ProposePeace { target: blue }), RuleViolation::FactionBound));
+
+        supervisor.shut_down_all().await;
+    }
+
+    #[tokio::test]
+    async fn paused_universe_only_gets_to_die() {
+        let _running = RUNNING.lock().await;
+        let (mut supervisor, [me, foe, friend, ..]) = supervisor().await;
+        supervisor.paused.insert(me);
+
+        for intent in [
+            UniverseIntent::Attack { target: foe, damage: 5 },
+            UniverseIntent::Heal { target: friend, amount: 5 },
+            UniverseIntent::Ping { target: friend },
+            UniverseIntent::DeclareWar { target: friend },
+            UniverseIntent::ProposeAlliance { target: foe },
+            UniverseIntent::ProposePeace { target: foe },
+        ] {
+            assert!(matches!(supervisor.check_intent(me, &intent), Err(RuleViolation::SourcePaused)), "{:?} passed", intent);
+        }
+        assert!(supervisor.check_intent(me, &UniverseIntent::Dead { target: me }).is_ok());
+
+        supervisor.shut_down_all().await;
+    }
+}
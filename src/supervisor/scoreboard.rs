@@ -0,0 +1,325 @@
+﻿use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use crate::logging::{LoggedEvent, SimulationEvent, UniverseRef, UniverseScore};
+use crate::supervisor::combat_stats::CombatStats;
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::overview::StatusBoard;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{Relationship, UniverseId};
+
+/// where a match stands. it starts once two universes are hostile,
+/// and is over once no hostile pair is left alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchPhase {
+    #[default]
+    Waiting,
+    Running { since: u64 },
+    Over { at: u64 },
+}
+
+/// keeps the score of every universe that took part in the match, fallen ones included,
+/// and what each did over the whole run for the leaderboard.
+/// the supervisor feeds it as it applies intents, front-ends rebuild it from the log bus.
+#[derive(Debug, Default)]
+pub struct MatchTracker {
+    pub phase: MatchPhase,
+    scores: BTreeMap<UniverseId, UniverseScore>,
+    run: BTreeMap<UniverseId, CombatStats>,
+    last_hit_by: HashMap<UniverseId, (UniverseId, u64)>, // the kill goes to whoever hit last, at that tick
+}
+
+impl MatchTracker {
+    pub fn joined(&mut self, universe: &UniverseRef, tick: u64) {
+        self.scores.insert(universe.id, UniverseScore {
+            id: universe.id,
+            name: universe.name.clone(),
+            faction: universe.faction.as_ref().map(|f| f.name.clone()),
+            damage_dealt: 0,
+            damage_taken: 0,
+            heals_given: 0,
+            kills: 0,
+            joined_at: tick,
+            fell_at: None,
+            survival_ticks: 0,
+        });
+        self.run.insert(universe.id, CombatStats {
+            universe: universe.clone(),
+            damage_dealt: 0,
+            damage_taken: 0,
+            heals_given: 0,
+            heals_received: 0,
+            kills: 0,
+            joined_at: tick,
+            fell_at: None,
+            uptime: 0,
+        });
+    }
+
+    /// once the match is over the board is final, until the next one starts
+    fn is_over(&self) -> bool {
+        matches!(self.phase, MatchPhase::Over { .. })
+    }
+
+    pub fn attack(&mut self, source: UniverseId, target: UniverseId, damage: i32, tick: u64) {
+        if let Some(stats) = self.run.get_mut(&source) {
+            stats.damage_dealt += damage;
+        }
+        if let Some(stats) = self.run.get_mut(&target) {
+            stats.damage_taken += damage;
+        }
+        self.last_hit_by.insert(target, (source, tick));
+
+        if self.is_over() {
+            return;
+        }
+        if let Some(score) = self.scores.get_mut(&source) {
+            score.damage_dealt += damage;
+        }
+        if let Some(score) = self.scores.get_mut(&target) {
+            score.damage_taken += damage;
+        }
+    }
+
+    pub fn heal(&mut self, source: UniverseId, target: UniverseId, amount: i32) {
+        if let Some(stats) = self.run.get_mut(&source) {
+            stats.heals_given += amount;
+        }
+        if let Some(stats) = self.run.get_mut(&target) {
+            stats.heals_received += amount;
+        }
+
+        if self.is_over() {
+            return;
+        }
+        if let Some(score) = self.scores.get_mut(&source) {
+            score.heals_given += amount;
+        }
+    }
+
+    pub fn fell(&mut self, id: UniverseId, tick: u64) {
+        let killer = self.last_hit_by.remove(&id);
+        if let Some(stats) = self.run.get_mut(&id) {
+            stats.fell_at = Some(tick);
+        }
+        if let Some((killer, _)) = killer
+            && let Some(stats) = self.run.get_mut(&killer) {
+            stats.kills += 1;
+        }
+
+        let since = match self.phase {
+            MatchPhase::Over { .. } => {
+                // not part of the finished board anymore, nor of the next match
+                self.scores.remove(&id);
+                return;
+            }
+            MatchPhase::Running { since } => since,
+            MatchPhase::Waiting => 0,
+        };
+        if let Some(score) = self.scores.get_mut(&id) {
+            score.fell_at = Some(tick);
+        }
+        // a hit from before this match started does not count for it
+        if let Some((killer, hit_at)) = killer
+            && hit_at >= since
+            && let Some(score) = self.scores.get_mut(&killer) {
+            score.kills += 1;
+        }
+    }
+
+    /// a new match among the living, everyone starts from zero
+    fn restart(&mut self, tick: u64) {
+        self.scores.retain(|_, score| score.fell_at.is_none());
+        for score in self.scores.values_mut() {
+            *score = UniverseScore {
+                id: score.id,
+                name: score.name.clone(),
+                faction: score.faction.clone(),
+                damage_dealt: 0,
+                damage_taken: 0,
+                heals_given: 0,
+                kills: 0,
+                joined_at: tick,
+                fell_at: None,
+                survival_ticks: 0,
+            };
+        }
+        self.phase = MatchPhase::Running { since: tick };
+    }
+
+    pub fn clear(&mut self) {
+        *self = MatchTracker::default();
+    }
+
+    /// follows a logged event, for front-ends that only see the log bus
+    pub fn observe(&mut self, entry: &LoggedEvent) {
+        match &entry.event {
+            SimulationEvent::Created { universe } => self.joined(universe, entry.tick),
+            SimulationEvent::Attack { source, target, damage } => self.attack(source.id, target.id, *damage, entry.tick),
+            SimulationEvent::Heal { source, target, amount } => self.heal(source.id, target.id, *amount),
+            SimulationEvent::Collapsed { universe } => self.fell(universe.id, entry.tick),
+            SimulationEvent::Rewound { .. } | SimulationEvent::Reset { .. } => self.clear(),
+            _ => {}
+        }
+    }
+
+    /// survivors first, then by kills and damage dealt
+    pub fn scoreboard(&self, tick: u64) -> Vec<UniverseScore> {
+        let tick = match self.phase {
+            MatchPhase::Over { at } => at,
+            _ => tick,
+        };
+        let mut board: Vec<UniverseScore> = self.scores.values().cloned()
+            .map(|mut score| {
+                score.survival_ticks = score.fell_at.unwrap_or(tick).saturating_sub(score.joined_at);
+                score
+            })
+            .collect();
+        board.sort_by_key(|s| (s.fell_at.is_some(), std::cmp::Reverse((s.kills, s.damage_dealt, s.survival_ticks))));
+        board
+    }
+
+    /// the whole run of every universe, in id order
+    pub fn stats(&self, tick: u64) -> Vec<CombatStats> {
+        self.run.values().cloned()
+            .map(|mut stats| {
+                stats.uptime = stats.fell_at.unwrap_or(tick).saturating_sub(stats.joined_at);
+                stats
+            })
+            .collect()
+    }
+}
+
+/// the scoreboard as a plain text table
+pub fn scoreboard_lines(board: &[UniverseScore]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:<16} {:<10} {:>6} {:>6} {:>6} {:>5} {:>9}",
+        "universe", "faction", "dealt", "taken", "healed", "kills", "survived",
+    )];
+    for score in board {
+        lines.push(format!(
+            "{:<16} {:<10} {:>6} {:>6} {:>6} {:>5} {:>9}{}",
+            score.name,
+            score.faction.as_deref().unwrap_or("-"),
+            score.damage_dealt,
+            score.damage_taken,
+            score.heals_given,
+            score.kills,
+            score.survival_ticks,
+            if score.fell_at.is_some() { "" } else { " *" },
+        ));
+    }
+    lines
+}
+
+/// whether two standing universes are still hostile to each other
+fn hostilities_left(affinities: &HashMap<(UniverseId, UniverseId), i32>, status_board: &StatusBoard) -> bool {
+    affinities.iter().any(|(&(first, second), &affinity)| {
+        Relationship::from_affinity(affinity).is_hostile()
+            && status_board.is_standing(first)
+            && status_board.is_standing(second)
+    })
+}
+
+/// writes the scoreboard to `path`: json for `.json` files, a text table otherwise
+pub async fn write_scoreboard(path: &Path, board: &[UniverseScore]) -> std::io::Result<()> {
+    let contents = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        serde_json::to_string_pretty(board)?
+    } else {
+        scoreboard_lines(board).join("\n") + "\n"
+    };
+    tokio::fs::write(path, contents).await
+}
+
+impl SupervisorHandle {
+    pub fn scoreboard(&self) -> Vec<UniverseScore> {
+        self.match_tracker.scoreboard(self.clock.tick())
+    }
+
+    /// after every tick: a match starts once two living universes are hostile, and ends once none are
+    pub(crate) async fn update_match(&mut self) {
+        let tick = self.clock.tick();
+        let hostile = hostilities_left(&self.affinities, &self.status_board);
+
+        match self.match_tracker.phase {
+            MatchPhase::Waiting if hostile => {
+                self.match_tracker.phase = MatchPhase::Running { since: tick };
+                Log::info(format!("The match is on at tick {}", tick));
+            }
+            MatchPhase::Over { .. } if hostile => {
+                self.match_tracker.restart(tick);
+                Log::info(format!("A new match is on at tick {}", tick));
+            }
+            MatchPhase::Running { .. } if !hostile => {
+                self.match_tracker.phase = MatchPhase::Over { at: tick };
+                self.end_match().await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn end_match(&self) {
+        let winners = self.status_board.standing();
+        let board = self.scoreboard();
+
+        if let Some(path) = &self.scoreboard_file
+            && let Err(e) = write_scoreboard(path, &board).await {
+            Log::info(format!("Scoreboard not written to {}: {}", path.display(), e));
+        }
+        Log::match_over(winners, board);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rgb::RGB8;
+    use crate::universe::UniverseStatus;
+    use super::*;
+
+    fn universe(id: UniverseId, name: &str) -> UniverseRef {
+        UniverseRef { id, name: name.to_owned(), color: RGB8::new(255, 255, 255), faction: None }
+    }
+
+    /// a and b are at war, c is allied to both
+    fn three_universes() -> (StatusBoard, HashMap<(UniverseId, UniverseId), i32>) {
+        let mut status_board = StatusBoard::default();
+        for (id, name) in [(1, "a"), (2, "b"), (3, "c")] {
+            status_board.joined(universe(id, name), 100, UniverseStatus::Running);
+        }
+        let affinities = HashMap::from([((1, 2), -80), ((1, 3), 40), ((2, 3), 40)]);
+        (status_board, affinities)
+    }
+
+    fn names(universes: Vec<UniverseRef>) -> Vec<String> {
+        universes.into_iter().map(|u| u.name).collect()
+    }
+
+    #[test]
+    fn collapsed_universe_is_never_a_winner() {
+        let (mut status_board, affinities) = three_universes();
+        assert!(hostilities_left(&affinities, &status_board));
+
+        // collapsed by the user with hp left, still on the board until it is removed
+        status_board.update(1, None, UniverseStatus::Collapsing);
+        assert!(!hostilities_left(&affinities, &status_board));
+        assert_eq!(names(status_board.standing()), ["b", "c"]);
+    }
+
+    #[test]
+    fn universe_without_hp_is_never_a_winner() {
+        let (mut status_board, affinities) = three_universes();
+
+        status_board.update(2, Some(0), UniverseStatus::Running);
+        assert!(!hostilities_left(&affinities, &status_board));
+        assert_eq!(names(status_board.standing()), ["a", "c"]);
+    }
+
+    #[test]
+    fn removed_universe_ends_the_war() {
+        let (mut status_board, affinities) = three_universes();
+
+        // its affinities may still be around when the match is checked
+        status_board.remove(2);
+        assert!(!hostilities_left(&affinities, &status_board));
+        assert_eq!(names(status_board.standing()), ["a", "c"]);
+    }
+}
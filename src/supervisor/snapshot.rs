@@ -0,0 +1,380 @@
+﻿use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use crate::supervisor::error::SnapshotError;
+use crate::supervisor::faction::faction_ref;
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::affinity::relationship_key;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::universe::{self, AFFINITY_RANGE, Relationship, UniverseStatus, TargetingKind, UniverseId, UniverseScript, UniverseSnapshot};
+
+/// bump whenever the format changes, and teach `upgrade` to bring the older version along.
+/// 2 added targeting, scripts, affinities and factions.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// the whole multiverse as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiverseSnapshot {
+    pub version: u32,
+    pub tick: u64,
+    pub next_universe_id: UniverseId,
+    pub universes: Vec<SavedUniverse>,
+    pub affinities: Vec<SavedAffinity>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedAffinity {
+    pub first: UniverseId,
+    pub second: UniverseId,
+    pub score: i32,
+}
+
+/// what an old save's plain enemies and brothers are worth
+const SAVED_ENEMY_AFFINITY: i32 = -80;
+const SAVED_BROTHER_AFFINITY: i32 = 80;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedUniverse {
+    pub id: UniverseId,
+    pub name: String,
+    pub color: RGB8,
+    pub hp: i32,
+    pub tick: i32,
+    pub executes: bool,
+    pub enemies: Vec<UniverseId>,
+    pub brothers: Vec<UniverseId>,
+    pub targeting: TargetingKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<PathBuf>, // compiled again on load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faction: Option<String>,
+}
+
+impl SavedUniverse {
+    fn state(&self) -> UniverseSnapshot {
+        UniverseSnapshot {
+            id: self.id,
+            hp: self.hp,
+            tick: self.tick,
+            executes: self.executes,
+            collapsed: false,
+            enemies: self.enemies.clone(),
+            brothers: self.brothers.clone(),
+            targeting: self.targeting,
+            world: None,
+            script: self.script.clone(),
+        }
+    }
+}
+
+impl SupervisorHandle {
+    /// asks every universe for its state and writes them all to `path`, returns how many were saved
+    pub async fn save_snapshot(&self, path: &Path) -> Result<usize, SnapshotError> {
+        let mut universes = Vec::new();
+        for (id, state) in self.query_snapshots().await {
+            let handle = &self.existing_universes[&id];
+
+            universes.push(SavedUniverse {
+                id,
+                name: handle.own_name.clone(),
+                color: handle.color,
+                hp: state.hp,
+                tick: state.tick,
+                executes: state.executes,
+                enemies: state.enemies,
+                brothers: state.brothers,
+                targeting: state.targeting,
+                script: state.script,
+                faction: handle.faction.as_ref().map(|f| f.name.clone()),
+            });
+        }
+
+        let mut affinities: Vec<SavedAffinity> = self.affinities.iter()
+            .map(|(&(first, second), &score)| SavedAffinity { first, second, score })
+            .collect();
+        affinities.sort_by_key(|a| (a.first, a.second));
+
+        let snapshot = MultiverseSnapshot {
+            version: SNAPSHOT_VERSION,
+            tick: self.clock.tick(),
+            next_universe_id: universe::peek_next_universe_id(),
+            universes,
+            affinities,
+        };
+
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        tokio::fs::write(path, json).await?;
+
+        Ok(snapshot.universes.len())
+    }
+
+    /// replaces the running multiverse with the one saved at `path`, returns how many universes were restored.
+    /// the file is fully checked before anything running is touched.
+    pub async fn load_snapshot(&mut self, path: &Path) -> Result<usize, SnapshotError> {
+        let json = tokio::fs::read_to_string(path).await?;
+        let snapshot = upgrade(serde_json::from_str(&json)?)?;
+        validate(&snapshot)?;
+
+        // scripts may have changed or vanished since the save
+        let mut scripts = HashMap::new();
+        for saved in &snapshot.universes {
+            if let Some(path) = &saved.script {
+                let script = UniverseScript::load(path).await
+                    .map_err(|e| SnapshotError::Invalid(format!("script of '{}': {}", saved.name, e)))?;
+                scripts.insert(saved.id, script);
+            }
+        }
+
+        // out with the old multiverse
+        self.shut_down_all().await;
+
+        // ids keep counting from where the saved run was, restored ones must never be handed out again
+        let max_id = snapshot.universes.iter().map(|u| u.id).max().unwrap_or(0);
+        universe::set_next_universe_id(snapshot.next_universe_id.max(max_id + 1));
+        self.clock.set_tick(snapshot.tick);
+        Log::reset(snapshot.tick);
+
+        for saved in &snapshot.universes {
+            let (universe_handle, intent_rx) = universe::restore_universe_handle(
+                saved.name.clone(), saved.color, &saved.state(), scripts.remove(&saved.id),
+                saved.faction.as_deref().map(faction_ref), &self.seed,
+            );
+
+            Log::created(universe_handle.universe_ref());
+            self.match_tracker.joined(&universe_handle.universe_ref(), snapshot.tick);
+            // joined like a new universe, then told how it really is
+            let status = if saved.executes { UniverseStatus::Running } else { UniverseStatus::Paused };
+            self.status_board.joined(universe_handle.universe_ref(), 100, UniverseStatus::Running);
+            self.update_status(saved.id, Some(saved.hp), status);
+
+            if !saved.executes {
+                self.paused.insert(saved.id);
+            }
+
+            self.universes_via_name.insert(saved.name.clone(), saved.id);
+            self.intents.insert(saved.id, UnboundedReceiverStream::new(intent_rx));
+            self.existing_universes.insert(saved.id, universe_handle);
+        }
+
+        // announced like new ones, so whoever follows the log knows them too
+        for saved in &snapshot.affinities {
+            self.affinities.insert(relationship_key(saved.first, saved.second), saved.score);
+            Log::relationship_announcement(
+                self.get_universe_ref_by_id(&saved.first),
+                self.get_universe_ref_by_id(&saved.second),
+                Relationship::from_affinity(saved.score),
+                saved.score,
+            );
+        }
+
+        Ok(snapshot.universes.len())
+    }
+}
+
+/// reads a snapshot of any known version, older ones are migrated to the current format first
+fn upgrade(mut json: Value) -> Result<MultiverseSnapshot, SnapshotError> {
+    match json.get("version").and_then(Value::as_u64) {
+        Some(1) => migrate_v1(&mut json),
+        Some(version) if version == SNAPSHOT_VERSION as u64 => {}
+        Some(version) => return Err(SnapshotError::UnsupportedVersion(version as u32)),
+        None => return Err(SnapshotError::Invalid("no version".to_owned())),
+    }
+    Ok(serde_json::from_value(json)?)
+}
+
+/// version 1 came before strategies, scripts, affinities and factions: everyone gets the
+/// default strategy, and plain enemies and brothers a fixed affinity. fields that a later
+/// version 1 file already has are kept.
+fn migrate_v1(json: &mut Value) {
+    let mut affinities = BTreeMap::new();
+    for saved in json["universes"].as_array_mut().into_iter().flatten() {
+        let Some(id) = saved["id"].as_u64() else {
+            continue;
+        };
+        for (side, affinity) in [("enemies", SAVED_ENEMY_AFFINITY), ("brothers", SAVED_BROTHER_AFFINITY)] {
+            for other in saved[side].as_array().into_iter().flatten().filter_map(Value::as_u64) {
+                affinities.insert(relationship_key(id as UniverseId, other as UniverseId), affinity);
+            }
+        }
+        if let Some(fields) = saved.as_object_mut() {
+            fields.entry("targeting").or_insert(json!(TargetingKind::default()));
+        }
+    }
+
+    if let Some(fields) = json.as_object_mut() {
+        let derived: Vec<Value> = affinities.into_iter()
+            .map(|((first, second), score)| json!({ "first": first, "second": second, "score": score }))
+            .collect();
+        fields.entry("affinities").or_insert(Value::Array(derived));
+        fields.insert("version".to_owned(), json!(SNAPSHOT_VERSION));
+    }
+}
+
+fn validate(snapshot: &MultiverseSnapshot) -> Result<(), SnapshotError> {
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+    }
+
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    for saved in &snapshot.universes {
+        if !ids.insert(saved.id) {
+            return Err(SnapshotError::Invalid(format!("universe id {} appears twice", saved.id)));
+        }
+        if !names.insert(saved.name.as_str()) {
+            return Err(SnapshotError::Invalid(format!("universe name '{}' appears twice", saved.name)));
+        }
+    }
+
+    // relationships may only point at universes that are part of the snapshot
+    for saved in &snapshot.universes {
+        if let Some(unknown) = saved.enemies.iter().chain(&saved.brothers).find(|id| !ids.contains(*id)) {
+            return Err(SnapshotError::Invalid(format!("'{}' relates to unknown universe {}", saved.name, unknown)));
+        }
+    }
+    for saved in &snapshot.affinities {
+        if !ids.contains(&saved.first) || !ids.contains(&saved.second) || saved.first == saved.second {
+            return Err(SnapshotError::Invalid(format!("affinity between {} and {} is not a known pair", saved.first, saved.second)));
+        }
+        if !AFFINITY_RANGE.contains(&saved.score) {
+            return Err(SnapshotError::Invalid(format!("affinity {} is out of range", saved.score)));
+        }
+    }
+
+    // what each universe believes must match the affinity of the pair
+    let mut relationships = HashMap::new();
+    for saved in &snapshot.affinities {
+        let key = relationship_key(saved.first, saved.second);
+        if relationships.insert(key, Relationship::from_affinity(saved.score)).is_some() {
+            return Err(SnapshotError::Invalid(format!("affinity between {} and {} appears twice", saved.first, saved.second)));
+        }
+    }
+    for saved in &snapshot.universes {
+        for &other in ids.iter().filter(|&&other| other != saved.id) {
+            let relationship = relationships.get(&relationship_key(saved.id, other));
+            let hostile = relationship.is_some_and(Relationship::is_hostile);
+            let friendly = relationship.is_some_and(Relationship::is_friendly);
+            if saved.enemies.contains(&other) != hostile || saved.brothers.contains(&other) != friendly {
+                return Err(SnapshotError::Invalid(format!(
+                    "'{}' and universe {} disagree with their affinity ({})",
+                    saved.name, other, relationship.map_or("none".to_owned(), |r| format!("{:?}", r)),
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{ClockMode, SimulationSeed};
+    use crate::supervisor::perception::PerceptionConfig;
+    use crate::supervisor::supervisor_task::tests::RUNNING;
+    use crate::universe::UniverseOptions;
+    use super::*;
+
+    fn saved(id: UniverseId, name: &str, enemies: &[UniverseId], brothers: &[UniverseId]) -> SavedUniverse {
+        SavedUniverse {
+            id,
+            name: name.to_owned(),
+            color: RGB8::new(255, 255, 255),
+            hp: 100,
+            tick: 0,
+            executes: true,
+            enemies: enemies.to_vec(),
+            brothers: brothers.to_vec(),
+            targeting: TargetingKind::default(),
+            script: None,
+            faction: None,
+        }
+    }
+
+    /// a and b are enemies, c keeps to itself
+    fn snapshot() -> MultiverseSnapshot {
+        MultiverseSnapshot {
+            version: SNAPSHOT_VERSION,
+            tick: 12,
+            next_universe_id: 4,
+            universes: vec![saved(1, "a", &[2], &[]), saved(2, "b", &[1], &[]), saved(3, "c", &[], &[])],
+            affinities: vec![SavedAffinity { first: 1, second: 2, score: -80 }],
+        }
+    }
+
+    #[test]
+    fn version_1_is_upgraded() {
+        let v1 = json!({
+            "version": 1,
+            "tick": 40,
+            "next_universe_id": 4,
+            "universes": [
+                { "id": 1, "name": "a", "color": { "r": 1, "g": 2, "b": 3 }, "hp": 90, "tick": 40, "executes": true, "enemies": [2], "brothers": [3] },
+                { "id": 2, "name": "b", "color": { "r": 1, "g": 2, "b": 3 }, "hp": 70, "tick": 40, "executes": false, "enemies": [1], "brothers": [] },
+                { "id": 3, "name": "c", "color": { "r": 1, "g": 2, "b": 3 }, "hp": 50, "tick": 40, "executes": true, "enemies": [], "brothers": [1] },
+            ],
+        });
+
+        let snapshot = upgrade(v1).unwrap();
+        validate(&snapshot).unwrap();
+
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert!(snapshot.universes.iter().all(|u| u.targeting == TargetingKind::default() && u.faction.is_none()));
+        let affinities: Vec<_> = snapshot.affinities.iter().map(|a| (a.first, a.second, a.score)).collect();
+        assert_eq!(affinities, [(1, 2, SAVED_ENEMY_AFFINITY), (1, 3, SAVED_BROTHER_AFFINITY)]);
+    }
+
+    #[test]
+    fn unknown_version_is_refused() {
+        let future = json!({ "version": SNAPSHOT_VERSION + 1 });
+        assert!(matches!(upgrade(future), Err(SnapshotError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn dangling_affinity_is_rejected() {
+        let mut snapshot = snapshot();
+        snapshot.affinities.push(SavedAffinity { first: 3, second: 9, score: 10 });
+        assert!(matches!(validate(&snapshot), Err(SnapshotError::Invalid(_))));
+    }
+
+    #[test]
+    fn duplicate_name_is_rejected() {
+        let mut snapshot = snapshot();
+        snapshot.universes.push(saved(4, "a", &[], &[]));
+        assert!(matches!(validate(&snapshot), Err(SnapshotError::Invalid(_))));
+    }
+
+    #[test]
+    fn contradicting_affinity_is_rejected() {
+        let mut snapshot = snapshot();
+        snapshot.affinities[0].score = 80;
+        assert!(matches!(validate(&snapshot), Err(SnapshotError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn save_and_load_keep_affinities_and_factions() {
+        let _running = RUNNING.lock().await;
+        let path = std::env::temp_dir().join(format!("multiverse-{}.json", std::process::id()));
+
+        let mut saved_run = SupervisorHandle::new(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        for (name, faction) in [("r1", Some("red")), ("r2", Some("red")), ("b1", Some("blue")), ("loner", None)] {
+            let options = UniverseOptions { faction: faction.map(str::to_owned), ..UniverseOptions::default() };
+            saved_run.add_new_universe(name.to_owned(), options).await;
+        }
+        assert_eq!(saved_run.save_snapshot(&path).await.unwrap(), 4);
+
+        let mut loaded_run = SupervisorHandle::new(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        let restored = loaded_run.load_snapshot(&path).await;
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(restored.unwrap(), 4);
+        assert_eq!(loaded_run.affinities, saved_run.affinities);
+        assert_eq!(loaded_run.factions(), saved_run.factions());
+        assert_eq!(loaded_run.factions()["red"], ["r1", "r2"]);
+
+        saved_run.shut_down_all().await;
+        loaded_run.shut_down_all().await;
+    }
+}
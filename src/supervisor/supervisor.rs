@@ -1,29 +1,85 @@
-﻿use std::collections::HashMap;
+﻿use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Instant;
+use rand::Rng;
+use rand::rngs::StdRng;
 use rgb::RGB8;
+use tokio::sync::oneshot;
+use tokio_stream::StreamMap;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use crate::logging::UniverseRef;
 use crate::supervisor::log_messages::Log;
-use crate::supervisor::error::UniverseLookupError;
+use crate::supervisor::error::{SupervisorError, UniverseLookupError};
+use crate::supervisor::faction::faction_ref;
+use crate::supervisor::intent_queue::{DrainPolicy, QueueMetrics};
+use crate::supervisor::perception::{Perception, PerceptionConfig};
+use crate::supervisor::overview::StatusBoard;
+use crate::supervisor::scoreboard::MatchTracker;
+use crate::supervisor::universe_report::UniverseReport;
+use crate::simulation::{ClockMode, SimulationClock, SimulationSeed};
 use crate::universe;
-use crate::universe::{UniverseCommand, UniverseEvent, UniverseHandle, UniverseId};
-use crate::universe::Relationship;
+use crate::universe::{DiplomaticAction, UniverseStatus, UniverseCommand, UniverseEvent, UniverseHandle, UniverseId, UniverseOptions, UniverseScript, UniverseSnapshot};
+use crate::universe::AFFINITY_RANGE;
 use crate::universe::UniverseIntent;
 
 pub struct SupervisorHandle {
     pub(crate) existing_universes: HashMap<UniverseId, UniverseHandle>,
     pub(crate) universes_via_name: HashMap<String, UniverseId>,
+    /// intent receivers of every living universe, polled together by the supervisor task
+    pub(crate) intents: StreamMap<UniverseId, UnboundedReceiverStream<UniverseIntent>>,
+    pub(crate) drain_policy: DrainPolicy,
+    pub(crate) drain_cursor: usize,
+    pub(crate) queue_metrics: HashMap<UniverseId, QueueMetrics>,
+    pending_pings: HashMap<u64, PendingPing>,
+    next_ping_nonce: u64,
+    pub(crate) seed: SimulationSeed,
+    rng: StdRng,
+    pub(crate) clock: SimulationClock,
+    pub(crate) perception_config: PerceptionConfig,
+    pub(crate) perception: Perception,
+    /// how every pair feels about each other, the rules check intents against it
+    pub(crate) affinities: HashMap<(UniverseId, UniverseId), i32>,
+    pub(crate) paused: HashSet<UniverseId>,
+    pub(crate) match_tracker: MatchTracker,
+    pub(crate) status_board: StatusBoard,
+    pub(crate) scoreboard_file: Option<PathBuf>, // written whenever a match ends
+}
+
+/// a ping that was forwarded to its target and waits for the pong
+struct PendingPing {
+    source: UniverseId,
+    target: UniverseId,
+    sent_at: Instant,
 }
 
 impl SupervisorHandle {
-    pub fn new() -> SupervisorHandle {
+    pub fn new(seed: SimulationSeed, clock_mode: ClockMode, perception_config: PerceptionConfig, scoreboard_file: Option<PathBuf>) -> SupervisorHandle {
         SupervisorHandle {
             existing_universes: HashMap::new(),
             universes_via_name: HashMap::new(),
+            intents: StreamMap::new(),
+            drain_policy: DrainPolicy::default(),
+            drain_cursor: 0,
+            queue_metrics: HashMap::new(),
+            pending_pings: HashMap::new(),
+            next_ping_nonce: 0,
+            seed,
+            rng: seed.supervisor_rng(),
+            clock: SimulationClock::new(clock_mode),
+            perception_config,
+            perception: Perception::default(),
+            affinities: HashMap::new(),
+            paused: HashSet::new(),
+            match_tracker: MatchTracker::default(),
+            status_board: StatusBoard::default(),
+            scoreboard_file,
         }
     }
 
     ///------------------------
     /// get universes
     ///------------------------
-    fn get_universe_handle_by_name(
+    pub(crate) fn get_universe_handle_by_name(
         &self,
         name: &str
     ) -> Result<&UniverseHandle, UniverseLookupError> {
@@ -34,10 +90,19 @@ impl SupervisorHandle {
 
         self.existing_universes
             .get(universe_id)
-            .ok_or_else(|| UniverseLookupError::UniverseNotFoundForId(universe_id.clone()))
+            .ok_or(UniverseLookupError::UniverseNotFoundForId(*universe_id))
     }
 
-    fn get_universe_name_by_id(
+    pub(crate) fn get_universe_handle_by_id(
+        &self,
+        universe_id: UniverseId
+    ) -> Result<&UniverseHandle, UniverseLookupError> {
+        self.existing_universes
+            .get(&universe_id)
+            .ok_or(UniverseLookupError::UniverseNotFoundForId(universe_id))
+    }
+
+    pub(crate) fn get_universe_name_by_id(
         &self,
         source_id: &UniverseId
     ) -> String {
@@ -47,42 +112,84 @@ impl SupervisorHandle {
             .unwrap_or(source_id.to_string())
     }
 
-    pub fn get_all_existing_universes(&self) -> Vec<&String> {
-        self.universes_via_name.keys().collect()
+    pub fn get_all_existing_universes(&self) -> Vec<String> {
+        self.universes_via_name.keys().cloned().collect()
+    }
+
+    pub fn get_universe_ref_by_name(&self, name: &str) -> Option<UniverseRef> {
+        self.get_universe_handle_by_name(name).ok().map(|h| h.universe_ref())
     }
 
-    fn get_color_by_id(&self, id: &UniverseId) -> RGB8 {
+    pub(crate) fn get_universe_ref_by_id(&self, id: &UniverseId) -> UniverseRef {
         self.existing_universes
             .get(id)
-            .map(|h| h.color)
-            .unwrap_or(RGB8::new(255, 255, 255))
+            .map(|h| h.universe_ref())
+            .unwrap_or(UniverseRef {
+                id: *id,
+                name: self.get_universe_name_by_id(id),
+                color: RGB8::new(255, 255, 255),
+                faction: None,
+            })
     }
 
     ///------------------------
     /// manage from UI
     ///------------------------
-    pub async fn add_new_universe(&mut self, name: String) {
+    pub async fn add_new_universe(&mut self, name: String, options: UniverseOptions) {
         // check if exists
         if self.universes_via_name.contains_key(&name) {
             Log::info(format!("Universe {} already exists!", name));
             return;
         }
         
+        // compile its script first, a broken one means no universe
+        let script = match &options.script {
+            Some(path) => match UniverseScript::load(path).await {
+                Ok(script) => Some(script),
+                Err(e) => {
+                    Log::info(format!("Universe {} not created, script error: {}", name, e));
+                    return;
+                }
+            },
+            None => None,
+        };
+
         // new universe
-        let universe_handle = universe::create_universe_handle(name.to_owned());
+        let faction = options.faction.as_deref().map(faction_ref);
+        let (universe_handle, intent_rx) = universe::create_universe_handle(name.to_owned(), &options, script, faction, &self.seed);
 
         // Log
-        Log::created(&name, universe_handle.color);
-
-        // declare brothers or enemies
-        self.roll_brothers_enemies_on_new_universe(&universe_handle).await;
+        Log::created(universe_handle.universe_ref());
+        self.match_tracker.joined(&universe_handle.universe_ref(), self.clock.tick());
+        self.status_board.joined(universe_handle.universe_ref(), 100, UniverseStatus::Running);
 
         // add to universe db
-        (&mut self.universes_via_name).insert(name, universe_handle.handle_id.clone());
-        (&mut self.existing_universes).insert(universe_handle.handle_id.clone(), universe_handle);
+        let id = universe_handle.handle_id;
+        self.universes_via_name.insert(name, id);
+        self.intents.insert(id, UnboundedReceiverStream::new(intent_rx));
+        self.existing_universes.insert(id, universe_handle);
+
+        // declare brothers or enemies
+        self.roll_brothers_enemies_on_new_universe(id).await;
     }
 
-    pub async fn send_universe_command(&self, universe_name: String, command: UniverseCommand) {
+    pub async fn send_universe_command(&mut self, universe_name: String, command: UniverseCommand) {
+        // the rules need to know who is paused
+        if let Some(&id) = self.universes_via_name.get(&universe_name) {
+            match command {
+                UniverseCommand::Start => {
+                    self.paused.remove(&id);
+                    self.update_status(id, None, UniverseStatus::Running);
+                }
+                UniverseCommand::Stop => {
+                    self.paused.insert(id);
+                    self.update_status(id, None, UniverseStatus::Paused);
+                }
+                UniverseCommand::Shutdown => self.update_status(id, None, UniverseStatus::Collapsing),
+                _ => {}
+            }
+        }
+
         // get universe
         let universe = match self.get_universe_handle_by_name(&universe_name) {
             Ok(u) => u,
@@ -98,20 +205,110 @@ impl SupervisorHandle {
         }
     }
 
+    /// asks the universe task for a snapshot of itself, and resolves its relationships to names
+    pub async fn query_universe_state(&self, universe_name: &str) -> Result<UniverseReport, UniverseLookupError> {
+        let universe = self.get_universe_handle_by_name(universe_name)?;
+
+        // ask the universe, it answers on the oneshot between two steps
+        let (reply_tx, reply_rx) = oneshot::channel();
+        universe.commander_tx.send(UniverseCommand::QueryState(reply_tx)).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(universe.handle_id))?;
+        let snapshot = reply_rx.await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(universe.handle_id))?;
+
+        Ok(UniverseReport {
+            id: snapshot.id,
+            name: universe.own_name.clone(),
+            color: universe.color,
+            faction: universe.faction.as_ref().map(|f| f.name.clone()),
+            hp: snapshot.hp,
+            tick: snapshot.tick,
+            executes: snapshot.executes,
+            collapsed: snapshot.collapsed,
+            enemies: snapshot.enemies.iter().map(|id| self.get_universe_name_by_id(id)).collect(),
+            brothers: snapshot.brothers.iter().map(|id| self.get_universe_name_by_id(id)).collect(),
+            targeting: snapshot.targeting,
+            script: snapshot.script,
+            seen_at: snapshot.world.as_ref().map(|world| world.tick),
+            sees: snapshot.world.map(|world| world.neighbours.into_iter()
+                .map(|(id, view)| (self.get_universe_name_by_id(&id), view))
+                .collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// the state of every living universe, in id order.
+    /// universes collapsing while asked are left out.
+    pub(crate) async fn query_snapshots(&self) -> Vec<(UniverseId, UniverseSnapshot)> {
+        let mut ids: Vec<UniverseId> = self.existing_universes.keys().copied().collect();
+        ids.sort();
+
+        let mut snapshots = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            if self.existing_universes[&id].commander_tx.send(UniverseCommand::QueryState(reply_tx)).await.is_err() {
+                continue;
+            }
+            let Ok(state) = reply_rx.await else {
+                continue;
+            };
+            if !state.collapsed {
+                snapshots.push((id, state));
+            }
+        }
+        snapshots
+    }
+
+    /// makes one universe ping another, the round trip is logged when the pong comes back
+    pub async fn send_ping(&self, source_name: &str, target_name: &str) -> Result<(), UniverseLookupError> {
+        let target_id = self.get_universe_handle_by_name(target_name)?.handle_id;
+        let source = self.get_universe_handle_by_name(source_name)?;
+
+        source.commander_tx.send(UniverseCommand::SendPing(target_id)).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(source.handle_id))
+    }
+
     ///------------------------
     /// runtime
     ///------------------------
-
+    /// applies an intent, or logs why it was dropped. intents may still be in flight
+    /// toward (or from) a universe that collapsed meanwhile, that is no reason to crash.
     pub async fn process_intent(&mut self, source_id: UniverseId, intent: UniverseIntent) {
+        let description = self.describe_intent(&intent);
+
+        if let Err(e) = self.apply_intent(source_id, intent).await {
+            Log::rejected(self.get_universe_ref_by_id(&source_id), description, e.to_string());
+        }
+    }
+
+    async fn apply_intent(&mut self, source_id: UniverseId, intent: UniverseIntent) -> Result<(), SupervisorError> {
+        self.check_intent(source_id, &intent)?;
+
         match intent {
             UniverseIntent::Attack { target, damage } => {
-                self.attack_intent(source_id, target, damage).await;
+                self.attack_intent(source_id, target, damage).await
             }
             UniverseIntent::Heal { target, amount } => {
-                self.heal_intent(source_id, target, amount).await;
+                self.heal_intent(source_id, target, amount).await
             }
             UniverseIntent::Dead { target } => {
-                self.collapsed_intent(target).await;
+                self.collapsed_intent(target).await
+            }
+            UniverseIntent::Ping { target } => {
+                self.ping_intent(source_id, target).await
+            }
+            UniverseIntent::Pong { target, nonce } => {
+                self.pong_intent(source_id, target, nonce);
+                Ok(())
+            }
+            UniverseIntent::DeclareWar { target } => {
+                self.diplomacy_intent(source_id, target, DiplomaticAction::DeclareWar).await
+            }
+            UniverseIntent::ProposeAlliance { target } => {
+                self.diplomacy_intent(source_id, target, DiplomaticAction::Ally).await
+            }
+            UniverseIntent::ProposePeace { target } => {
+                self.diplomacy_intent(source_id, target, DiplomaticAction::Peace).await
             }
         }
     }
@@ -123,43 +320,49 @@ impl SupervisorHandle {
         &mut self,
         source_id: UniverseId,
         target_id: UniverseId,
-        damage: i32) {
-        // log attack
-        let source_name = self.get_universe_name_by_id(&source_id);
-        let target_name = self.get_universe_name_by_id(&target_id);
-        let source_handle = self.existing_universes.get(&source_id).unwrap();
-        let target_handle = self.existing_universes.get(&target_id).unwrap();
+        damage: i32) -> Result<(), SupervisorError> {
+        let source_handle = self.get_universe_handle_by_id(source_id)?;
+        let target_handle = self.get_universe_handle_by_id(target_id)?;
 
-        Log::attack(&source_name, source_handle.color, &target_name, target_handle.color, damage);
+        // send the universe the hit, log it once it is on its way
+        target_handle.commander_tx.send(UniverseCommand::InjectEvent(UniverseEvent::Attacked { by: source_id, damage })).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(target_id))?;
 
-        // send the universe shatter command
-        self.send_universe_command(target_name, UniverseCommand::InjectEvent(UniverseEvent::Shatter(damage))).await;
+        Log::attack(source_handle.universe_ref(), target_handle.universe_ref(), damage);
+        self.match_tracker.attack(source_id, target_id, damage, self.clock.tick());
+
+        // the hit has landed, a relationship that could not follow does not undo it
+        self.drift_after_attack(source_id, target_id, damage).await;
+        Ok(())
     }
 
     pub async fn heal_intent(
         &mut self,
         source_id: UniverseId,
         target_id: UniverseId,
-        amount: i32) {
-        // log attack
-        let source_name = self.get_universe_name_by_id(&source_id);
-        let target_name = self.get_universe_name_by_id(&target_id);
-        let source_handle = self.existing_universes.get(&source_id).unwrap();
-        let target_handle = self.existing_universes.get(&target_id).unwrap();
+        amount: i32) -> Result<(), SupervisorError> {
+        let source_handle = self.get_universe_handle_by_id(source_id)?;
+        let target_handle = self.get_universe_handle_by_id(target_id)?;
 
-        Log::heal(&source_name, source_handle.color, &target_name, target_handle.color, amount);
+        // send the universe the heal, log it once it is on its way
+        target_handle.commander_tx.send(UniverseCommand::InjectEvent(UniverseEvent::Heal(amount))).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(target_id))?;
 
-        // send the universe heal command
-        self.send_universe_command(target_name, UniverseCommand::InjectEvent(UniverseEvent::Heal(amount))).await;
+        Log::heal(source_handle.universe_ref(), target_handle.universe_ref(), amount);
+        self.match_tracker.heal(source_id, target_id, amount);
+
+        self.drift_after_heal(source_id, target_id, amount).await;
+        Ok(())
     }
 
     pub async fn collapsed_intent(
         &mut self,
-        target_id: UniverseId,) {
+        target_id: UniverseId,) -> Result<(), SupervisorError> {
         let target_name = self.get_universe_name_by_id(&target_id);
-        let target_handle = self.existing_universes.get(&target_id).unwrap();
+        let target_handle = self.get_universe_handle_by_id(target_id)?;
 
-        Log::collapsed(&target_name, target_handle.color);
+        Log::collapsed(target_handle.universe_ref());
+        self.match_tracker.fell(target_id, self.clock.tick());
 
         // broadcast everyone it collapsed
         self.broadcast_collapsed_universe(target_id);
@@ -168,8 +371,71 @@ impl SupervisorHandle {
         self.send_universe_command(target_name, UniverseCommand::Shutdown).await;
 
         // remove from own hashmaps
-        self.existing_universes.remove(&target_id);
+        let fallen = self.existing_universes.remove(&target_id);
+        self.intents.remove(&target_id);
+        self.queue_metrics.remove(&target_id);
+        self.status_board.remove(target_id);
         self.universes_via_name.retain(|_, &mut id| id != target_id);
+        self.affinities.retain(|&(first, second), _| first != target_id && second != target_id);
+        self.paused.remove(&target_id);
+        self.pending_pings.retain(|_, ping| ping.source != target_id && ping.target != target_id);
+
+        if let Some(fallen) = fallen {
+            self.check_faction_victory(&fallen);
+        }
+        Ok(())
+    }
+
+    /// collapsed on the user's order, it goes the same way as one that fell
+    pub async fn collapse_universe(&mut self, universe_name: &str) {
+        let Some(&id) = self.universes_via_name.get(universe_name) else {
+            return;
+        };
+        if let Err(e) = self.collapsed_intent(id).await {
+            Log::info(format!("Could not collapse {}: {}", universe_name, e));
+        }
+    }
+
+    pub async fn ping_intent(
+        &mut self,
+        source_id: UniverseId,
+        target_id: UniverseId) -> Result<(), SupervisorError> {
+        let target_tx = self.get_universe_handle_by_id(target_id)?.commander_tx.clone();
+
+        // remember when the ping left, the clock stops when the pong is processed
+        let nonce = self.next_ping_nonce;
+        self.next_ping_nonce += 1;
+        self.pending_pings.insert(nonce, PendingPing {
+            source: source_id,
+            target: target_id,
+            sent_at: Instant::now(),
+        });
+
+        target_tx.send(
+            UniverseCommand::InjectEvent(UniverseEvent::Ping { from: source_id, nonce }),
+        ).await
+            .map_err(|_| UniverseLookupError::UniverseUnresponsive(target_id))?;
+        Ok(())
+    }
+
+    pub fn pong_intent(
+        &mut self,
+        source_id: UniverseId,
+        target_id: UniverseId,
+        nonce: u64) {
+        // the pong source is the pinged universe, its target the original pinger
+        let Some(ping) = self.pending_pings.remove(&nonce) else {
+            return;
+        };
+        if ping.source != target_id || ping.target != source_id {
+            return;
+        }
+
+        Log::pong(
+            self.get_universe_ref_by_id(&ping.source),
+            self.get_universe_ref_by_id(&ping.target),
+            ping.sent_at.elapsed(),
+        );
     }
 
     fn broadcast_collapsed_universe(&self, collapsed_id: UniverseId) {
@@ -182,74 +448,61 @@ impl SupervisorHandle {
         }
     }
 
-    pub async fn roll_brothers_enemies_on_new_universe(&mut self, universe_handle: &UniverseHandle) {
-        if self.existing_universes.len() == 0 {
-            // this is the first universe
-            return;
-        }
-
+    pub async fn roll_brothers_enemies_on_new_universe(&mut self, new_id: UniverseId) {
         // roll enemy or friend on random
-        let all_universes_ids: Vec<UniverseId> = self.existing_universes.keys().cloned().collect();
+        // sorted, so the same seed rolls the same relationships
+        let mut all_universes_ids: Vec<UniverseId> = self.existing_universes.keys().cloned()
+            .filter(|&id| id != new_id)
+            .collect();
+        all_universes_ids.sort();
 
         // Send command to set relationships
         for target_id in all_universes_ids {
-            // 50/50 enemy or brother
-            if rand::random() {
-                self.set_relationship(&universe_handle, target_id, Relationship::Enemy).await;
+            // factions decide for their members, the rest roll
+            // 50/50 enemy-ish or brother-ish, how much so is rolled too
+            let affinity = if let Some(affinity) = self.faction_affinity(target_id, new_id) {
+                affinity
+            } else if self.rng.random() {
+                self.rng.random_range(*AFFINITY_RANGE.start()..=-20)
             } else {
-                self.set_relationship(&universe_handle, target_id, Relationship::Brother).await;
+                self.rng.random_range(20..=*AFFINITY_RANGE.end())
+            };
+
+            if let Err(e) = self.set_affinity(target_id, new_id, affinity).await {
+                Log::info(format!("No relationship with #{}: {}", target_id, e));
             }
         }
     }
 
-    async fn set_relationship(&mut self, universe_handle: &UniverseHandle, target_id: UniverseId, relationship: Relationship) {
-        let _ = universe_handle.commander_tx.try_send(
-            UniverseCommand::SetRelationship(target_id, relationship)
-        );
-        let _ = self.get_universe_handle_by_name(self.get_universe_name_by_id(&target_id).as_str()).unwrap()
-            .commander_tx.try_send(
-            UniverseCommand::SetRelationship(universe_handle.handle_id, relationship)
-        );
-
-        // log
-        self.log_relationship(universe_handle, target_id, relationship);
+    pub fn does_universe_exist(&self, universe_name: &str) -> bool {
+        self.universes_via_name.contains_key(universe_name)
     }
 
-    fn log_relationship(&mut self, universe_handle: &UniverseHandle, target_id: UniverseId, relationship: Relationship) {
-        let own_name = universe_handle.own_name.clone();
-        let target_name = self.get_universe_name_by_id(&target_id);
-
-        let new_color = universe_handle.color;
-        let target_color = self.get_color_by_id(&target_id);
-
-        match relationship {
-            Relationship::Enemy => {
-                Log::relationship_announcement(
-                    &target_name, target_color,
-                    &own_name, new_color,
-                    "ENEMIES",
-                    "⚔️ War has been declared",
-                );
-            }
-            Relationship::Brother => {
-                Log::relationship_announcement(
-                    &target_name, target_color,
-                    &own_name, new_color,
-                    "BROTHERS",
-                    "An alliance forged in the void",
-                );
-            }
+    /// for when shutting down system
+    pub async fn shut_down_all(&mut self) {
+        // in id order, so the collapses land in the log the same way every run
+        let mut universes: Vec<(UniverseId, String)> = self.universes_via_name.iter()
+            .map(|(name, &id)| (id, name.clone()))
+            .collect();
+        universes.sort();
+        for (_id, universe_name) in universes {
+            self.send_universe_command(universe_name, UniverseCommand::Shutdown).await;
         }
-    }
 
-    pub fn does_universe_exist(&self, universe_name: String) -> bool {
-        self.universes_via_name.contains_key(&universe_name)
+        self.wait_for_all_tasks_to_finish().await;
     }
-    
-    /// for when shutting down system
-    pub async fn wait_for_all_tasks_to_finish(&mut self) {
+
+    async fn wait_for_all_tasks_to_finish(&mut self) {
         for (_id, universe) in self.existing_universes.drain() {
             let _ = universe.universe_task_handle.await;
         }
+        self.universes_via_name.clear();
+        self.intents.clear();
+        self.queue_metrics.clear();
+        self.perception.clear();
+        self.affinities.clear();
+        self.paused.clear();
+        self.match_tracker.clear();
+        self.status_board.clear();
     }
 }
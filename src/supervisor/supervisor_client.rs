@@ -0,0 +1,138 @@
+﻿use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot};
+use crate::supervisor::error::{SnapshotError, UniverseLookupError};
+use crate::supervisor::combat_stats::CombatStats;
+use crate::supervisor::intent_queue::QueueMetrics;
+use crate::supervisor::supervisor_command::SupervisorCommand;
+use crate::supervisor::universe_report::UniverseReport;
+use crate::logging::{UniverseRef, UniverseScore};
+use crate::simulation::ClockMode;
+use crate::universe::{DiplomaticAction, UniverseCommand, UniverseOptions};
+
+/// cheap, cloneable way to talk to the supervisor task.
+/// every front-end (the TUI, scripts, ...) goes through this.
+#[derive(Clone)]
+pub struct SupervisorClient {
+    command_tx: mpsc::Sender<SupervisorCommand>,
+}
+
+impl SupervisorClient {
+    pub(crate) fn new(command_tx: mpsc::Sender<SupervisorCommand>) -> Self {
+        SupervisorClient { command_tx }
+    }
+
+    async fn send(&self, command: SupervisorCommand) {
+        // the supervisor only goes away on shutdown, nothing left to do then
+        let _ = self.command_tx.send(command).await;
+    }
+
+    async fn request<T>(&self, build: impl FnOnce(oneshot::Sender<T>) -> SupervisorCommand) -> Option<T> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(build(reply_tx)).await;
+        reply_rx.await.ok()
+    }
+
+    pub async fn new_universe(&self, name: String, options: UniverseOptions) {
+        self.send(SupervisorCommand::NewUniverse { name, options }).await;
+    }
+
+    pub async fn send_universe_command(&self, name: String, command: UniverseCommand) {
+        self.send(SupervisorCommand::UniverseCommand { name, command }).await;
+    }
+
+    pub async fn list_universes(&self) -> Vec<String> {
+        self.request(|reply| SupervisorCommand::ListUniverses { reply }).await
+            .unwrap_or_default()
+    }
+
+    pub async fn does_universe_exist(&self, name: String) -> bool {
+        self.request(|reply| SupervisorCommand::UniverseExists { name, reply }).await
+            .unwrap_or(false)
+    }
+
+    /// id, name and colour of a universe, for logging about it
+    pub async fn universe_ref(&self, name: String) -> Option<UniverseRef> {
+        self.request(|reply| SupervisorCommand::UniverseRef { name, reply }).await
+            .flatten()
+    }
+
+    pub async fn query_universe_state(&self, name: String) -> Result<UniverseReport, UniverseLookupError> {
+        let lookup_name = name.clone();
+        self.request(|reply| SupervisorCommand::QueryState { name, reply }).await
+            .unwrap_or(Err(UniverseLookupError::IdNotFoundForName(lookup_name)))
+    }
+
+    pub async fn set_clock(&self, mode: ClockMode) {
+        self.send(SupervisorCommand::SetClock { mode }).await;
+    }
+
+    /// current clock mode and tick
+    pub async fn clock_status(&self) -> Option<(ClockMode, u64)> {
+        self.request(|reply| SupervisorCommand::ClockStatus { reply }).await
+    }
+
+    /// advances the simulation by `ticks`, resolves once every universe stepped
+    pub async fn step(&self, ticks: u64) -> Option<u64> {
+        self.request(|reply| SupervisorCommand::Step { ticks, reply }).await
+    }
+
+    /// intent queue depth and throughput of every universe
+    pub async fn queue_metrics(&self) -> Vec<(String, QueueMetrics)> {
+        self.request(|reply| SupervisorCommand::QueueMetrics { reply }).await
+            .unwrap_or_default()
+    }
+
+    pub async fn send_ping(&self, source: String, target: String) -> Result<(), UniverseLookupError> {
+        let lookup_name = source.clone();
+        self.request(|reply| SupervisorCommand::Ping { source, target, reply }).await
+            .unwrap_or(Err(UniverseLookupError::IdNotFoundForName(lookup_name)))
+    }
+
+    /// what every universe of the run did so far, fallen ones included
+    pub async fn combat_stats(&self) -> Vec<CombatStats> {
+        self.request(|reply| SupervisorCommand::CombatStats { reply }).await
+            .unwrap_or_default()
+    }
+
+    /// how every universe of the current (or last) match did
+    pub async fn scoreboard(&self) -> Vec<UniverseScore> {
+        self.request(|reply| SupervisorCommand::Scoreboard { reply }).await
+            .unwrap_or_default()
+    }
+
+    /// every faction with a living member, and its members
+    pub async fn factions(&self) -> BTreeMap<String, Vec<String>> {
+        self.request(|reply| SupervisorCommand::Factions { reply }).await
+            .unwrap_or_default()
+    }
+
+    /// `source` declares war on, or proposes to, `target`. the outcome shows up in the log.
+    pub async fn send_diplomacy(&self, source: String, target: String, action: DiplomaticAction) -> Result<(), UniverseLookupError> {
+        let lookup_name = source.clone();
+        self.request(|reply| SupervisorCommand::Diplomacy { source, target, action, reply }).await
+            .unwrap_or(Err(UniverseLookupError::IdNotFoundForName(lookup_name)))
+    }
+
+    /// saves the whole multiverse to a file, answers with the number of universes saved
+    pub async fn save(&self, path: PathBuf) -> Result<usize, SnapshotError> {
+        self.request(|reply| SupervisorCommand::Save { path, reply }).await
+            .unwrap_or_else(|| Err(supervisor_gone()))
+    }
+
+    /// replaces the running multiverse with a saved one
+    pub async fn load(&self, path: PathBuf) -> Result<usize, SnapshotError> {
+        self.request(|reply| SupervisorCommand::Load { path, reply }).await
+            .unwrap_or_else(|| Err(supervisor_gone()))
+    }
+
+    /// collapses every universe, resolves once the supervisor task is done with them
+    pub async fn shutdown(&self) {
+        self.request(|reply| SupervisorCommand::Shutdown { reply }).await;
+    }
+}
+
+fn supervisor_gone() -> SnapshotError {
+    SnapshotError::Io(io::Error::other("supervisor is not running"))
+}
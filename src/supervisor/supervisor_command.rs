@@ -0,0 +1,34 @@
+﻿use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+use crate::supervisor::error::{SnapshotError, UniverseLookupError};
+use crate::supervisor::combat_stats::CombatStats;
+use crate::supervisor::intent_queue::QueueMetrics;
+use crate::supervisor::universe_report::UniverseReport;
+use crate::logging::{UniverseRef, UniverseScore};
+use crate::simulation::ClockMode;
+use crate::universe::{DiplomaticAction, UniverseCommand, UniverseOptions};
+
+/// requests sent from a front-end to the supervisor task.
+/// the ones that expect an answer carry a oneshot to reply on.
+#[derive(Debug)]
+pub enum SupervisorCommand {
+    NewUniverse { name: String, options: UniverseOptions },
+    UniverseCommand { name: String, command: UniverseCommand },
+    ListUniverses { reply: oneshot::Sender<Vec<String>> },
+    UniverseExists { name: String, reply: oneshot::Sender<bool> },
+    UniverseRef { name: String, reply: oneshot::Sender<Option<UniverseRef>> },
+    QueryState { name: String, reply: oneshot::Sender<Result<UniverseReport, UniverseLookupError>> },
+    SetClock { mode: ClockMode },
+    ClockStatus { reply: oneshot::Sender<(ClockMode, u64)> },
+    Step { ticks: u64, reply: oneshot::Sender<u64> }, // answers with the tick reached
+    CombatStats { reply: oneshot::Sender<Vec<CombatStats>> },
+    Scoreboard { reply: oneshot::Sender<Vec<UniverseScore>> },
+    Factions { reply: oneshot::Sender<BTreeMap<String, Vec<String>>> }, // faction name to member names
+    QueueMetrics { reply: oneshot::Sender<Vec<(String, QueueMetrics)>> },
+    Ping { source: String, target: String, reply: oneshot::Sender<Result<(), UniverseLookupError>> },
+    Diplomacy { source: String, target: String, action: DiplomaticAction, reply: oneshot::Sender<Result<(), UniverseLookupError>> },
+    Save { path: PathBuf, reply: oneshot::Sender<Result<usize, SnapshotError>> },
+    Load { path: PathBuf, reply: oneshot::Sender<Result<usize, SnapshotError>> },
+    Shutdown { reply: oneshot::Sender<()> }, // collapses every universe and stops the supervisor
+}
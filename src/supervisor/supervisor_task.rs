@@ -0,0 +1,251 @@
+﻿use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+use crate::supervisor::log_messages::Log;
+use crate::supervisor::perception::PerceptionConfig;
+use crate::supervisor::supervisor::SupervisorHandle;
+use crate::supervisor::supervisor_client::SupervisorClient;
+use crate::supervisor::supervisor_command::SupervisorCommand;
+use crate::simulation::{ClockMode, SimulationSeed};
+use crate::universe::{UniverseCommand, UniverseStatus};
+
+/// spawns the supervisor as its own task, owning every universe.
+/// returns a client to talk to it and the task handle to await on exit.
+pub fn spawn_supervisor(seed: SimulationSeed, clock_mode: ClockMode, perception: PerceptionConfig, scoreboard_file: Option<PathBuf>) -> (SupervisorClient, JoinHandle<()>) {
+    let (command_tx, command_rx) = mpsc::channel::<SupervisorCommand>(64);
+
+    let supervisor = SupervisorHandle::new(seed, clock_mode, perception, scoreboard_file);
+    let supervisor_task_handle = tokio::spawn(supervisor.run(command_rx));
+
+    (SupervisorClient::new(command_tx), supervisor_task_handle)
+}
+
+impl SupervisorHandle {
+    /// the supervisor acting as a server: waits on front-end commands,
+    /// on intents from all universes and on its clock, whichever comes first.
+    pub async fn run(mut self, mut command_rx: mpsc::Receiver<SupervisorCommand>) {
+        loop {
+            tokio::select! {
+                command = command_rx.recv() => {
+                    match command {
+                        Some(command) => {
+                            if self.handle_command(command).await {
+                                return;
+                            }
+                        }
+                        None => {
+                            // every client is gone, nobody can talk to us anymore
+                            self.shut_down_all().await;
+                            return;
+                        }
+                    }
+                }
+
+                _ = self.clock.wait_next_tick() => {
+                    self.advance_tick().await;
+                }
+
+                // woken up by any intent, then catch up on everything that is queued.
+                // in step mode intents wait for the next step, so runs stay deterministic
+                Some((source_id, intent)) = self.intents.next(),
+                    if !self.intents.is_empty() && self.clock.mode() != ClockMode::Stepped => {
+                    self.record_processed_intent(source_id);
+                    self.process_intent(source_id, intent).await;
+                    self.drain_intents().await;
+                }
+            }
+        }
+    }
+
+    /// one simulation tick: every universe steps (in id order), their intents are processed,
+    /// then they may get to see each other
+    async fn advance_tick(&mut self) {
+        self.clock.advance();
+
+        let mut ids: Vec<_> = self.existing_universes.keys().copied().collect();
+        ids.sort();
+
+        let mut pending_steps = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (done_tx, done_rx) = oneshot::channel();
+            if self.existing_universes[&id].commander_tx.send(UniverseCommand::Tick(done_tx)).await.is_ok() {
+                pending_steps.push((id, done_rx));
+            } else {
+                // its task stopped answering, it is on its way out
+                self.update_status(id, None, UniverseStatus::Collapsing);
+            }
+        }
+
+        // a universe that died meanwhile drops its sender, that's fine
+        for (id, done_rx) in pending_steps {
+            match done_rx.await {
+                Ok(report) => self.update_status(id, Some(report.hp), report.status),
+                Err(_) => self.update_status(id, None, UniverseStatus::Collapsing),
+            }
+        }
+
+        self.drain_intents().await;
+        self.update_perception().await;
+        self.update_match().await;
+    }
+
+    /// returns true once the supervisor has shut down
+    async fn handle_command(&mut self, command: SupervisorCommand) -> bool {
+        match command {
+            SupervisorCommand::NewUniverse { name, options } => {
+                self.add_new_universe(name, options).await;
+            }
+            SupervisorCommand::UniverseCommand { name, command: UniverseCommand::Shutdown } => {
+                self.collapse_universe(&name).await;
+            }
+            SupervisorCommand::UniverseCommand { name, command } => {
+                self.send_universe_command(name, command).await;
+            }
+            SupervisorCommand::ListUniverses { reply } => {
+                let _ = reply.send(self.get_all_existing_universes());
+            }
+            SupervisorCommand::UniverseExists { name, reply } => {
+                let _ = reply.send(self.does_universe_exist(&name));
+            }
+            SupervisorCommand::UniverseRef { name, reply } => {
+                let _ = reply.send(self.get_universe_ref_by_name(&name));
+            }
+            SupervisorCommand::QueryState { name, reply } => {
+                let _ = reply.send(self.query_universe_state(&name).await);
+            }
+            SupervisorCommand::SetClock { mode } => {
+                self.clock.set_mode(mode);
+                Log::info(format!("Clock set to {}", mode));
+            }
+            SupervisorCommand::ClockStatus { reply } => {
+                let _ = reply.send((self.clock.mode(), self.clock.tick()));
+            }
+            SupervisorCommand::Step { ticks, reply } => {
+                for _ in 0..ticks {
+                    self.advance_tick().await;
+                }
+                let _ = reply.send(self.clock.tick());
+            }
+            SupervisorCommand::QueueMetrics { reply } => {
+                let _ = reply.send(self.get_queue_metrics());
+            }
+            SupervisorCommand::Ping { source, target, reply } => {
+                let _ = reply.send(self.send_ping(&source, &target).await);
+            }
+            SupervisorCommand::CombatStats { reply } => {
+                let _ = reply.send(self.combat_stats());
+            }
+            SupervisorCommand::Scoreboard { reply } => {
+                let _ = reply.send(self.scoreboard());
+            }
+            SupervisorCommand::Factions { reply } => {
+                let _ = reply.send(self.factions());
+            }
+            SupervisorCommand::Diplomacy { source, target, action, reply } => {
+                let _ = reply.send(self.send_diplomacy(&source, &target, action).await);
+            }
+            SupervisorCommand::Save { path, reply } => {
+                let _ = reply.send(self.save_snapshot(&path).await);
+            }
+            SupervisorCommand::Load { path, reply } => {
+                let _ = reply.send(self.load_snapshot(&path).await);
+            }
+            SupervisorCommand::Shutdown { reply } => {
+                self.shut_down_all().await;
+                let _ = reply.send(());
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::sync::Mutex;
+    use crate::logging::{subscribe, SimulationEvent};
+    use crate::universe::{self, UniverseOptions};
+
+    /// universe ids and the log bus are shared by the whole test binary, one run at a time
+    pub(crate) static RUNNING: Mutex<()> = Mutex::const_new(());
+
+    /// three universes fighting on a stepped clock, every event of the run as text.
+    /// ticks are left out, the tick on the bus is shared by the whole test binary.
+    async fn seeded_run(seed: u64, ticks: u64) -> Vec<String> {
+        let _running = RUNNING.lock().await;
+        universe::set_next_universe_id(1); // the universe rngs come from the seed and the id
+        let mut log_receiver = subscribe();
+
+        let (client, task) = spawn_supervisor(SimulationSeed(seed), ClockMode::Stepped, PerceptionConfig::default(), None);
+        for name in ["alpha", "beta", "gamma"] {
+            client.new_universe(name.to_owned(), UniverseOptions::default()).await;
+        }
+        client.step(ticks).await;
+        client.shutdown().await;
+        task.await.unwrap();
+
+        let mut events = Vec::new();
+        while let Ok(entry) = log_receiver.try_recv() {
+            events.push(entry.event.to_string());
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn same_seed_same_battle() {
+        let first = seeded_run(7, 40).await;
+        let second = seeded_run(7, 40).await;
+
+        assert!(first.iter().any(|event| event.contains('→')), "nobody attacked: {:#?}", first);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn collapsed_universe_is_gone() {
+        let _running = RUNNING.lock().await;
+        let (client, task) = spawn_supervisor(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        for name in ["a", "b", "c"] {
+            client.new_universe(name.to_owned(), UniverseOptions::default()).await;
+        }
+        client.step(5).await;
+        assert!(client.does_universe_exist("a".to_owned()).await);
+
+        client.send_universe_command("a".to_owned(), UniverseCommand::Shutdown).await;
+        client.step(20).await;
+
+        let names = client.list_universes().await;
+        assert!(!names.contains(&"a".to_owned()), "still listed: {:?}", names);
+        assert!(!client.does_universe_exist("a".to_owned()).await);
+
+        client.shutdown().await;
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn collapsing_a_faction_through_manage_ends_it() {
+        let _running = RUNNING.lock().await;
+        let mut log_receiver = subscribe();
+        let (client, task) = spawn_supervisor(SimulationSeed(7), ClockMode::Stepped, PerceptionConfig::default(), None);
+        for (name, faction) in [("r1", "red"), ("r2", "red"), ("b1", "blue")] {
+            let options = UniverseOptions { faction: Some(faction.to_owned()), ..UniverseOptions::default() };
+            client.new_universe(name.to_owned(), options).await;
+        }
+
+        client.send_universe_command("b1".to_owned(), UniverseCommand::Shutdown).await;
+        client.step(1).await;
+        assert!(!client.factions().await.contains_key("blue"));
+
+        client.shutdown().await;
+        task.await.unwrap();
+
+        let mut victors = Vec::new();
+        while let Ok(entry) = log_receiver.try_recv() {
+            if let SimulationEvent::FactionVictory { faction, survivors } = entry.event {
+                victors.push((faction.name, survivors.into_iter().map(|u| u.name).collect::<Vec<_>>()));
+            }
+        }
+        assert_eq!(victors, [("red".to_owned(), vec!["r1".to_owned(), "r2".to_owned()])]);
+    }
+}
@@ -0,0 +1,53 @@
+﻿use std::path::PathBuf;
+use rgb::RGB8;
+use crate::universe::{NeighbourStatus, NeighbourView, TargetingKind, UniverseId};
+
+/// a universe snapshot as seen by the supervisor, with ids resolved to names.
+#[derive(Debug, Clone)]
+pub struct UniverseReport {
+    pub id: UniverseId,
+    pub name: String,
+    pub color: RGB8,
+    pub faction: Option<String>,
+    pub hp: i32,
+    pub tick: i32,
+    pub executes: bool,
+    pub collapsed: bool,
+    pub enemies: Vec<String>,
+    pub brothers: Vec<String>,
+    pub targeting: TargetingKind,
+    pub script: Option<PathBuf>,
+    pub seen_at: Option<u64>, // tick of what it currently sees of the others
+    pub sees: Vec<(String, NeighbourView)>,
+}
+
+impl UniverseReport {
+    pub fn status(&self) -> &'static str {
+        if self.collapsed {
+            "collapsed"
+        } else if self.executes {
+            "running"
+        } else {
+            "paused"
+        }
+    }
+
+    /// what picks its targets: the script, or the targeting strategy
+    pub fn tactics(&self) -> String {
+        match &self.script {
+            Some(path) => format!("script {}", path.display()),
+            None => self.targeting.to_string(),
+        }
+    }
+
+    /// `b 80hp, c 40hp paused`
+    pub fn sees_text(&self) -> String {
+        self.sees.iter()
+            .map(|(name, view)| match view.status {
+                NeighbourStatus::Running => format!("{} {}hp", name, view.hp),
+                NeighbourStatus::Paused => format!("{} {}hp paused", name, view.hp),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
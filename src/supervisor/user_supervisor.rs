@@ -1,21 +1,31 @@
-﻿use chrono::{Local, Timelike};
-use crate::supervisor::supervisor::SupervisorHandle;
-use crate::universe::{UniverseCommand, UniverseId, UniverseIntent};
+﻿use std::path::PathBuf;
+use chrono::{Local, Timelike};
+use tokio::task::JoinHandle;
+use crate::supervisor::perception::PerceptionConfig;
+use crate::supervisor::supervisor_client::SupervisorClient;
+use crate::supervisor::supervisor_task::spawn_supervisor;
+use crate::simulation::{ClockMode, SimulationSeed};
 
 pub struct UserSupervisor {
-    pub(crate) supervisor: SupervisorHandle,
+    pub(crate) client: SupervisorClient,
+    pub(crate) seed: SimulationSeed,
+    supervisor_task_handle: Option<JoinHandle<()>>,
 }
 
 impl UserSupervisor {
-    pub fn new() -> Self {
-        Self::print_greetings_message();
+    pub fn new(seed: SimulationSeed, clock_mode: ClockMode, perception: PerceptionConfig, scoreboard_file: Option<PathBuf>) -> Self {
+        Self::print_greetings_message(seed);
+
+        let (client, supervisor_task_handle) = spawn_supervisor(seed, clock_mode, perception, scoreboard_file);
 
         UserSupervisor {
-            supervisor: SupervisorHandle::new(),
+            client,
+            seed,
+            supervisor_task_handle: Some(supervisor_task_handle),
         }
     }
 
-    fn print_greetings_message() {
+    fn print_greetings_message(seed: SimulationSeed) {
         let hour = Local::now().hour();
 
         let msg = match hour {
@@ -26,42 +36,16 @@ impl UserSupervisor {
         };
 
         println!("{} - Supervisor Initialized.", msg);
+        println!("Simulation seed: {}", seed.0);
         println!("Have a good rest of your day.");
     }
 
     // --- Helpers ---
-    pub(crate) async fn new_universe(&mut self, name: String) {
-        self.supervisor.add_new_universe(name.clone()).await;
-    }
-
-    pub(crate) fn get_list_universes(&self) -> Vec<&String> {
-        self.supervisor.get_all_existing_universes()
-    }
-
     pub(crate) async fn shut_down_all(&mut self) {
-        for (universe_name, _universe) in self.supervisor.universes_via_name.iter() {
-            self.supervisor.send_universe_command(universe_name.clone(), UniverseCommand::Shutdown).await;
-        }
+        self.client.shutdown().await;
 
-        self.supervisor.wait_for_all_tasks_to_finish().await;
-    }
-
-    /// gets called in the main loop, this is the supervisor acting as a server,
-    /// checking for incoming messages (intents) from the universes and processing them.
-    pub(crate) async fn process_universe_events(&mut self) {
-        // collect all intents
-        let mut pending_intents: Vec<(UniverseId, UniverseIntent)> = Vec::new();
-
-        // Drain pending intents from all universes
-        for handle in self.supervisor.existing_universes.values_mut() {
-            if let Ok(intent) = handle.intent_rx.try_recv() {
-                pending_intents.push((handle.handle_id, intent));
-            }
-        }
-
-        // process all intents
-        for (source_id, intent) in pending_intents {
-            self.supervisor.process_intent(source_id, intent).await;
+        if let Some(supervisor_task_handle) = self.supervisor_task_handle.take() {
+            let _ = supervisor_task_handle.await;
         }
     }
-}
\ No newline at end of file
+}
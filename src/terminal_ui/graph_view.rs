@@ -0,0 +1,106 @@
+﻿use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::TAU;
+use std::time::{Duration, Instant};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    symbols::Marker,
+    text::Span,
+    widgets::{Block, Borders, canvas::{Canvas, Circle, Line}},
+};
+use crate::logging::{LoggedEvent, SimulationEvent, UniverseRef};
+use crate::terminal_ui::log_render::color;
+use crate::universe::{Relationship, UniverseId};
+
+/// how long an attack or heal lights up its edge
+pub const HIGHLIGHT_DURATION: Duration = Duration::from_millis(600);
+
+/// who is what to whom, rebuilt from the log bus so a replay draws it just like a live run
+#[derive(Debug, Default)]
+pub struct RelationshipGraph {
+    nodes: BTreeMap<UniverseId, UniverseRef>,
+    edges: BTreeMap<(UniverseId, UniverseId), Relationship>, // each pair once, lower id first
+}
+
+impl RelationshipGraph {
+    pub fn observe(&mut self, entry: &LoggedEvent) {
+        match &entry.event {
+            SimulationEvent::Created { universe } => {
+                self.nodes.insert(universe.id, universe.clone());
+            }
+            SimulationEvent::RelationshipSet { first, second, relationship, .. } => {
+                let pair = (first.id.min(second.id), first.id.max(second.id));
+                self.edges.insert(pair, *relationship);
+            }
+            SimulationEvent::Collapsed { universe } => {
+                self.nodes.remove(&universe.id);
+                self.edges.retain(|&(first, second), _| first != universe.id && second != universe.id);
+            }
+            SimulationEvent::Rewound { .. } | SimulationEvent::Reset { .. } => *self = RelationshipGraph::default(),
+            _ => {}
+        }
+    }
+}
+
+/// an attack or heal that just travelled between two universes
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeHighlight {
+    pub source: UniverseId,
+    pub target: UniverseId,
+    pub color: Color,
+    pub at: Instant,
+}
+
+/// draws universes on a circle, with their relationships as edges.
+/// edges in `highlights` are drawn over the others in their own colour.
+pub fn render_graph(f: &mut Frame, area: Rect, graph: &RelationshipGraph, highlights: &[EdgeHighlight]) {
+    let count = graph.nodes.len().max(1) as f64;
+    let positions: HashMap<UniverseId, (f64, f64)> = graph.nodes.values().enumerate()
+        .map(|(i, node)| {
+            // first one on top, going clockwise
+            let angle = TAU / 4.0 - TAU * i as f64 / count;
+            (node.id, (angle.cos(), angle.sin()))
+        })
+        .collect();
+
+    let canvas = Canvas::default()
+        .block(Block::default().borders(Borders::ALL).title("Relationships"))
+        .marker(Marker::Braille)
+        .x_bounds([-1.4, 1.4])
+        .y_bounds([-1.2, 1.2])
+        .paint(|ctx| {
+            let edge = |first: UniverseId, second: UniverseId, color: Color| {
+                let (&(x1, y1), &(x2, y2)) = (positions.get(&first)?, positions.get(&second)?);
+                Some(Line { x1, y1, x2, y2, color })
+            };
+
+            for (&(first, second), &relationship) in &graph.edges {
+                let color = match relationship {
+                    Relationship::Enemy => Color::Red,
+                    Relationship::Rival => Color::LightRed,
+                    Relationship::Neutral => continue,
+                    Relationship::Ally => Color::LightGreen,
+                    Relationship::Brother => Color::Green,
+                };
+                if let Some(line) = edge(first, second, color) {
+                    ctx.draw(&line);
+                }
+            }
+            ctx.layer();
+
+            for highlight in highlights {
+                if let Some(line) = edge(highlight.source, highlight.target, highlight.color) {
+                    ctx.draw(&line);
+                }
+            }
+            ctx.layer();
+
+            for node in graph.nodes.values() {
+                let (x, y) = positions[&node.id];
+                ctx.draw(&Circle { x, y, radius: 0.04, color: color(node.color) });
+                ctx.print(x + 0.06, y + 0.06, Span::styled(node.name.clone(), Style::default().fg(color(node.color))));
+            }
+        });
+    f.render_widget(canvas, area);
+}
@@ -0,0 +1,333 @@
+﻿use std::env;
+use std::fs;
+use std::path::PathBuf;
+use ratatui::{
+    Frame,
+    layout::{Position, Rect},
+    widgets::{Block, Borders, Paragraph},
+};
+use crate::supervisor::log_messages::Log;
+
+/// commands kept in the history and its file, the oldest go first
+const HISTORY_CAPACITY: usize = 1_000;
+
+/// where the history survives between sessions: `<config dir>/rust-multiverse/history`
+fn history_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("rust-multiverse").join("history"))
+}
+
+/// the input box: one editable line with a cursor, a history of submitted
+/// commands and completion of the word before the cursor
+pub struct InputLine {
+    text: String,
+    cursor: usize,               // byte offset, always on a char boundary
+    history: Vec<String>,
+    browsing: Option<usize>,     // history entry shown while going up and down
+    draft: String,               // what was typed before browsing started
+    history_file: Option<PathBuf>,
+}
+
+impl InputLine {
+    /// an input line with the history of earlier sessions
+    pub fn new() -> Self {
+        Self::with_history_file(history_path())
+    }
+
+    /// an input line keeping its history in `history_file`, or only in memory without one
+    pub fn with_history_file(history_file: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = history_file
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().filter(|l| !l.trim().is_empty()).map(str::to_owned).collect())
+            .unwrap_or_default();
+        if history.len() > HISTORY_CAPACITY {
+            history.drain(..history.len() - HISTORY_CAPACITY);
+        }
+        Self { text: String::new(), cursor: 0, history, browsing: None, draft: String::new(), history_file }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// empties the line without remembering it
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        self.browsing = None;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn clear(&mut self) {
+        self.take();
+    }
+
+    /// empties the line and adds it to the history
+    pub fn submit(&mut self) -> String {
+        let line = self.take();
+        let command = line.trim();
+        if !command.is_empty() && self.history.last().map(String::as_str) != Some(command) {
+            self.history.push(command.to_owned());
+            if self.history.len() > HISTORY_CAPACITY {
+                self.history.drain(..self.history.len() - HISTORY_CAPACITY);
+            }
+            self.save();
+        }
+        line
+    }
+
+    /// rewrites the history file, so it never holds more than the capped history
+    fn save(&self) {
+        let Some(path) = &self.history_file else {
+            return;
+        };
+        let contents = self.history.join("\n") + "\n";
+        let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(path, contents));
+        if let Err(e) = written {
+            Log::info(format!("Could not save command history to {}: {}", path.display(), e));
+        }
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// deletes the word before the cursor and the spaces after it, like ctrl-w in a shell
+    pub fn delete_word(&mut self) {
+        let start = self.word_start(self.text[..self.cursor].trim_end().len());
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    fn word_start(&self, end: usize) -> usize {
+        self.text[..end].rfind(char::is_whitespace).map_or(0, |i| i + 1)
+    }
+
+    /// shows the previous command, the line being typed is kept for coming back down
+    pub fn history_up(&mut self) {
+        let index = match self.browsing {
+            Some(0) => return,
+            Some(i) => i - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.browsing = Some(index);
+        self.show(self.history[index].clone());
+    }
+
+    pub fn history_down(&mut self) {
+        let Some(i) = self.browsing else {
+            return;
+        };
+        if i + 1 < self.history.len() {
+            self.browsing = Some(i + 1);
+            self.show(self.history[i + 1].clone());
+        } else {
+            self.browsing = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.show(draft);
+        }
+    }
+
+    fn show(&mut self, text: String) {
+        self.text = text;
+        self.cursor = self.text.len();
+    }
+
+    /// true while the cursor is in the first word, where command names go
+    pub fn completing_command(&self) -> bool {
+        !self.text[..self.cursor].trim_start().contains(char::is_whitespace)
+    }
+
+    /// completes the word before the cursor from the candidates.
+    /// a single match is filled in, several are filled up to what they share and returned.
+    pub fn complete(&mut self, candidates: &[String]) -> Vec<String> {
+        let start = self.word_start(self.cursor);
+        let prefix = self.text[start..self.cursor].to_lowercase();
+        let mut matches: Vec<String> = candidates
+            .iter()
+            .filter(|c| c.to_lowercase().starts_with(&prefix))
+            .cloned()
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        let completion = match matches.as_slice() {
+            [] => return matches,
+            [only] => format!("{} ", only),
+            [first, rest @ ..] => {
+                let shared = rest.iter().fold(first.len(), |len, other| {
+                    first.char_indices().zip(other.chars())
+                        .take_while(|((i, a), b)| *i < len && a.eq_ignore_ascii_case(b))
+                        .map(|((i, a), _)| i + a.len_utf8())
+                        .last()
+                        .unwrap_or(0)
+                });
+                first[..shared].to_owned()
+            }
+        };
+        if completion.len() >= self.cursor - start {
+            self.text.replace_range(start..self.cursor, &completion);
+            self.cursor = start + completion.len();
+        }
+        if matches.len() == 1 { Vec::new() } else { matches }
+    }
+
+    /// draws the line, scrolled so the cursor stays in the box
+    pub fn render(&self, f: &mut Frame, area: Rect, title: &str) {
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        let column = self.text[..self.cursor].chars().count();
+        let offset = column.saturating_sub(width - 1);
+        let visible: String = self.text.chars().skip(offset).collect();
+
+        let input = Paragraph::new(visible)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(input, area);
+        f.set_cursor_position(Position::new(area.x + 1 + (column - offset) as u16, area.y + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> InputLine {
+        let mut line = InputLine::with_history_file(None);
+        text.chars().for_each(|c| line.insert(c));
+        line
+    }
+
+    fn candidates(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    #[test]
+    fn single_match_is_filled_in() {
+        let mut line = line("sta");
+        assert!(line.complete(&candidates(&["status", "step", "list"])).is_empty());
+        assert_eq!(line.as_str(), "status ");
+    }
+
+    #[test]
+    fn several_matches_fill_what_they_share() {
+        let mut line = line("manage s");
+        let matches = line.complete(&candidates(&["stats", "state", "list"]));
+        assert_eq!(matches, ["state", "stats"]);
+        assert_eq!(line.as_str(), "manage stat");
+    }
+
+    #[test]
+    fn no_match_leaves_the_line() {
+        let mut line = line("xyz");
+        assert!(line.complete(&candidates(&["status"])).is_empty());
+        assert_eq!(line.as_str(), "xyz");
+    }
+
+    #[test]
+    fn delete_word_at_the_start_does_nothing() {
+        let mut line = line("new alpha");
+        line.home();
+        line.delete_word();
+        assert_eq!(line.as_str(), "new alpha");
+    }
+
+    #[test]
+    fn delete_word_takes_the_trailing_spaces() {
+        let mut line = line("new alpha  ");
+        line.delete_word();
+        assert_eq!(line.as_str(), "new ");
+        line.delete_word();
+        assert_eq!(line.as_str(), "");
+        line.delete_word();
+        assert_eq!(line.as_str(), "");
+    }
+
+    #[test]
+    fn delete_word_keeps_what_follows_the_cursor() {
+        let mut line = line("new alpha");
+        (0..6).for_each(|_| line.left());
+        line.delete_word();
+        assert_eq!(line.as_str(), " alpha");
+        line.insert('x');
+        assert_eq!(line.as_str(), "x alpha");
+    }
+
+    #[test]
+    fn history_goes_up_and_back_down_to_the_draft() {
+        let mut line = InputLine::with_history_file(None);
+        for command in ["new a", "new b", "new b", "  "] {
+            command.chars().for_each(|c| line.insert(c));
+            line.submit();
+        }
+        "dra".chars().for_each(|c| line.insert(c));
+
+        line.history_up();
+        assert_eq!(line.as_str(), "new b");
+        line.history_up();
+        assert_eq!(line.as_str(), "new a");
+        line.history_up();
+        assert_eq!(line.as_str(), "new a");
+
+        line.history_down();
+        assert_eq!(line.as_str(), "new b");
+        line.history_down();
+        assert_eq!(line.as_str(), "dra");
+        line.history_down();
+        assert_eq!(line.as_str(), "dra");
+    }
+
+    #[test]
+    fn history_file_is_capped() {
+        let path = env::temp_dir().join(format!("input-history-{}", std::process::id()));
+        fs::write(&path, (0..HISTORY_CAPACITY + 200).map(|i| format!("old {}\n", i)).collect::<String>()).unwrap();
+
+        let mut line = InputLine::with_history_file(Some(path.clone()));
+        "newest".chars().for_each(|c| line.insert(c));
+        line.submit();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let lines: Vec<&str> = saved.lines().collect();
+        assert_eq!(lines.len(), HISTORY_CAPACITY);
+        assert_eq!(lines[0], "old 201");
+        assert_eq!(lines.last(), Some(&"newest"));
+    }
+}
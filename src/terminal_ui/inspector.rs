@@ -0,0 +1,73 @@
+﻿use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+use crate::supervisor::universe_report::UniverseReport;
+
+const HP_BAR_WIDTH: i32 = 20;
+
+/// draws the live universe inspector, or an empty panel when nothing is inspected.
+pub fn render_inspector(f: &mut Frame, area: Rect, report: Option<&UniverseReport>) {
+    let Some(report) = report else {
+        let empty = Paragraph::new("type 'state' while managing a universe")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(Block::default().borders(Borders::ALL).title("Inspector"));
+        f.render_widget(empty, area);
+        return;
+    };
+
+    let color = Color::Rgb(report.color.r, report.color.g, report.color.b);
+    let filled = (report.hp.clamp(0, 100) * HP_BAR_WIDTH / 100) as usize;
+    let hp_color = match report.hp {
+        0..=25 => Color::Red,
+        26..=60 => Color::Yellow,
+        _ => Color::Green,
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("HP       "),
+            Span::styled("█".repeat(filled), Style::default().fg(hp_color)),
+            Span::styled("░".repeat(HP_BAR_WIDTH as usize - filled), Style::default().fg(Color::DarkGray)),
+            Span::raw(format!(" {}/100", report.hp)),
+        ]),
+        Line::from(format!("Tick     {}", report.tick)),
+        Line::from(format!("Status   {}", report.status())),
+        Line::from(format!("Tactics  {}", report.tactics())),
+        Line::from(format!("Faction  {}", report.faction.as_deref().unwrap_or("none"))),
+        Line::from(match report.seen_at {
+            Some(tick) => format!("Sees     {} (tick {})", report.sees_text(), tick),
+            None => "Sees     nothing yet".to_owned(),
+        }),
+        Line::from(vec![
+            Span::raw("Enemies  "),
+            Span::styled(names_or_none(&report.enemies), Style::default().fg(Color::Red)),
+        ]),
+        Line::from(vec![
+            Span::raw("Brothers "),
+            Span::styled(names_or_none(&report.brothers), Style::default().fg(Color::Green)),
+        ]),
+    ];
+
+    let title = Line::from(vec![
+        Span::raw("Inspector: "),
+        Span::styled(report.name.clone(), Style::default().fg(color)),
+        Span::raw(format!(" (#{})", report.id)),
+    ]);
+
+    let inspector = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    f.render_widget(inspector, area);
+}
+
+fn names_or_none(names: &[String]) -> String {
+    if names.is_empty() {
+        "-".to_owned()
+    } else {
+        names.join(", ")
+    }
+}
@@ -0,0 +1,49 @@
+﻿use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+};
+use crate::supervisor::combat_stats::{CombatStats, StatsColumn};
+use crate::terminal_ui::log_render::color;
+
+/// draws the live leaderboard, sorted by `sort`. `stats` is sorted in place.
+pub fn render_leaderboard(f: &mut Frame, area: Rect, stats: &mut [CombatStats], sort: StatsColumn) {
+    sort.sort(stats);
+
+    let header = Row::new(StatsColumn::ALL.map(|column| {
+        if column == sort {
+            Cell::from(format!("{}▼", column)).style(Style::default().fg(Color::Yellow))
+        } else {
+            Cell::from(column.to_string())
+        }
+    })).style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = stats.iter().map(|s| {
+        let name_style = match s.fell_at {
+            Some(_) => Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT),
+            None => Style::default().fg(color(s.universe.color)),
+        };
+        Row::new([
+            Cell::from(s.universe.name.clone()).style(name_style),
+            Cell::from(s.damage_dealt.to_string()),
+            Cell::from(s.damage_taken.to_string()),
+            Cell::from(s.heals_given.to_string()),
+            Cell::from(s.kills.to_string()),
+            Cell::from(s.uptime.to_string()),
+        ])
+    });
+
+    let widths = [
+        Constraint::Min(8),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(7),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Leaderboard"));
+    f.render_widget(table, area);
+}
@@ -0,0 +1,131 @@
+﻿use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use rgb::RGB8;
+use crate::logging::simulation_event::{diplomacy_wording, format_round_trip, relationship_wording};
+use crate::logging::{LoggedEvent, SimulationEvent, UniverseRef};
+
+pub fn color(rgb: RGB8) -> Color {
+    Color::Rgb(rgb.r, rgb.g, rgb.b)
+}
+
+/// universes of a faction wear its colour, the rest their own
+fn name(universe: &UniverseRef) -> Span<'static> {
+    let rgb = universe.faction.as_ref().map_or(universe.color, |faction| faction.color);
+    Span::styled(universe.name.clone(), Style::default().fg(color(rgb)))
+}
+
+/// the log panel's subscriber: turns an event into a coloured line
+pub fn render_event(entry: &LoggedEvent) -> Vec<Span<'static>> {
+    match &entry.event {
+        SimulationEvent::Info { message } => vec![
+            Span::styled("> ".to_owned(), Style::default().fg(Color::Cyan)),
+            Span::raw(message.clone()),
+        ],
+        SimulationEvent::Rewound { tick } => vec![
+            Span::styled(format!("⏪ Replay rewound to tick {tick}"), Style::default().fg(Color::Cyan)),
+        ],
+        SimulationEvent::Status { universe, hp, status } => vec![
+            name(universe),
+            Span::styled(format!(": {hp} hp, {status}"), Style::default().fg(Color::DarkGray)),
+        ],
+        SimulationEvent::Reset { tick } => vec![
+            Span::styled(format!("> Multiverse replaced by a snapshot at tick {tick}"), Style::default().fg(Color::Cyan)),
+        ],
+        SimulationEvent::Created { universe } => {
+            let mut spans = vec![
+                Span::styled("> Created universe ".to_owned(), Style::default().fg(Color::Cyan)),
+                name(universe),
+            ];
+            if let Some(faction) = &universe.faction {
+                spans.push(Span::styled(" for faction ".to_owned(), Style::default().fg(Color::Cyan)));
+                spans.push(Span::styled(faction.name.clone(), Style::default().fg(color(faction.color))));
+            }
+            spans
+        }
+        SimulationEvent::RelationshipSet { first, second, relationship, affinity } => {
+            let (label, subtitle) = relationship_wording(*relationship);
+            vec![
+                Span::styled("Relationship: ".to_owned(), Style::default().fg(Color::Cyan)),
+                name(first),
+                Span::raw(" and ".to_owned()),
+                name(second),
+                Span::styled(format!(" are {label} ({affinity:+}) "), Style::default().fg(Color::Yellow)),
+                Span::styled(subtitle.to_owned(), Style::default().fg(Color::DarkGray)),
+            ]
+        }
+        SimulationEvent::Attack { source, target, damage } => vec![
+            Span::raw("[".to_owned()),
+            name(source),
+            Span::raw("] → [".to_owned()),
+            name(target),
+            Span::styled(format!("] −{damage} HP"), Style::default().fg(Color::Red)),
+        ],
+        SimulationEvent::Heal { source, target, amount } => vec![
+            Span::raw("[".to_owned()),
+            name(source),
+            Span::raw("] healed [".to_owned()),
+            name(target),
+            Span::styled(format!("] +{amount} HP"), Style::default().fg(Color::Green)),
+        ],
+        SimulationEvent::Collapsed { universe } => vec![
+            Span::styled("☠ ".to_owned(), Style::default().fg(Color::Red)),
+            name(universe),
+            Span::styled(" has COLLAPSED".to_owned(), Style::default().fg(Color::DarkGray)),
+        ],
+        SimulationEvent::Pong { source, target, round_trip_us } => vec![
+            Span::raw("[".to_owned()),
+            name(source),
+            Span::raw("] ping ⇄ pong [".to_owned()),
+            name(target),
+            Span::styled(format!("] {}", format_round_trip(*round_trip_us)), Style::default().fg(Color::Magenta)),
+        ],
+        SimulationEvent::Rejected { source, intent, reason } => vec![
+            Span::styled("✋ [".to_owned(), Style::default().fg(Color::DarkGray)),
+            name(source),
+            Span::styled(format!("] {intent} rejected: {reason}"), Style::default().fg(Color::DarkGray)),
+        ],
+        SimulationEvent::Diplomacy { source, target, action, accepted } => vec![
+            Span::raw("🕊 [".to_owned()),
+            name(source),
+            Span::styled(
+                format!("] {} [", diplomacy_wording(*action, *accepted)),
+                Style::default().fg(if *accepted { Color::LightBlue } else { Color::DarkGray }),
+            ),
+            name(target),
+            Span::raw("]".to_owned()),
+        ],
+        SimulationEvent::FactionVictory { faction, survivors } => {
+            let mut spans = vec![
+                Span::styled("🏆 Faction ".to_owned(), Style::default().fg(Color::Yellow)),
+                Span::styled(faction.name.clone(), Style::default().fg(color(faction.color))),
+                Span::styled(" wins! Standing: ".to_owned(), Style::default().fg(Color::Yellow)),
+            ];
+            for (i, survivor) in survivors.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(", ".to_owned()));
+                }
+                spans.push(name(survivor));
+            }
+            spans
+        }
+        SimulationEvent::MatchOver { winners, .. } => {
+            let style = Style::default().fg(Color::Yellow);
+            if winners.is_empty() {
+                return vec![Span::styled("🏁 The match is over, nobody is left standing".to_owned(), style)];
+            }
+            let mut spans = vec![Span::styled("🏁 The match is over! Winners: ".to_owned(), style)];
+            for (i, winner) in winners.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(", ".to_owned()));
+                }
+                spans.push(name(winner));
+            }
+            spans
+        }
+        SimulationEvent::UserAction { actor, action, target } => vec![
+            Span::styled("> ".to_owned(), Style::default().fg(Color::Cyan)),
+            Span::styled(actor.clone(), Style::default().fg(color(target.color))),
+            Span::raw(format!(" {action} {}", target.name)),
+        ],
+    }
+}
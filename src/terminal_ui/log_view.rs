@@ -0,0 +1,374 @@
+﻿use std::collections::VecDeque;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+use crate::logging::LoggedEvent;
+use crate::terminal_ui::log_render::render_event;
+
+/// events kept for scrolling back, the oldest go first
+const LOG_CAPACITY: usize = 10_000;
+
+/// which events the log panel shows
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub kind: Option<String>,     // one of `SimulationEvent::KINDS`
+    pub universe: Option<String>, // only events about this universe
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LoggedEvent) -> bool {
+        // quiet events only show up when filtered for
+        if entry.event.is_quiet() && self.kind.as_deref() != Some(entry.event.kind()) {
+            return false;
+        }
+        if let Some(kind) = &self.kind
+            && entry.event.kind() != kind {
+            return false;
+        }
+        if let Some(name) = &self.universe
+            && !entry.event.universes().iter().any(|u| u.name.eq_ignore_ascii_case(name)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// the log panel: a ring of events that can be scrolled, searched and filtered.
+/// while following, the newest events stay in sight.
+/// how many events pass the filter and the search is counted as they come and go, not on every frame.
+pub struct LogView {
+    entries: VecDeque<LoggedEvent>,
+    follow: bool,
+    scroll: usize, // shown events hidden below the window
+    page: usize,   // rows of the last render, what PageUp/PageDown move by
+    search: Option<String>,
+    filter: LogFilter,
+    shown: usize,    // entries passing the filter
+    matching: usize, // shown entries matching the search
+}
+
+impl LogView {
+    pub fn new() -> Self {
+        LogView {
+            entries: VecDeque::new(),
+            follow: true,
+            scroll: 0,
+            page: 10,
+            search: None,
+            filter: LogFilter::default(),
+            shown: 0,
+            matching: 0,
+        }
+    }
+
+    pub fn push(&mut self, entry: LoggedEvent) {
+        if self.filter.matches(&entry) {
+            self.shown += 1;
+            self.matching += self.is_match(&entry) as usize;
+            // scrolled back, what is on screen stays put
+            if !self.follow {
+                self.scroll += 1;
+            }
+        }
+        if self.entries.len() == LOG_CAPACITY
+            && let Some(evicted) = self.entries.pop_front()
+            && self.filter.matches(&evicted) {
+            self.shown -= 1;
+            self.matching -= self.is_match(&evicted) as usize;
+        }
+        self.entries.push_back(entry);
+        self.scroll = self.scroll.min(self.shown.saturating_sub(1));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.scroll = 0;
+        self.shown = 0;
+        self.matching = 0;
+    }
+
+    pub fn page_up(&mut self) {
+        self.follow = false;
+        self.scroll = (self.scroll + self.page).min(self.shown.saturating_sub(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll = self.scroll.saturating_sub(self.page);
+        if self.scroll == 0 {
+            self.follow = true;
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.follow = false;
+        self.scroll = self.shown.saturating_sub(1);
+    }
+
+    pub fn end(&mut self) {
+        self.follow = true;
+        self.scroll = 0;
+    }
+
+    /// stop where we are, or jump back to the newest
+    pub fn toggle_follow(&mut self) {
+        if self.follow {
+            self.follow = false;
+        } else {
+            self.end();
+        }
+    }
+
+    pub fn filter(&self) -> &LogFilter {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: LogFilter) {
+        self.filter = filter;
+        self.recount();
+        self.end();
+    }
+
+    pub fn set_search(&mut self, search: Option<String>) {
+        if self.search != search {
+            self.search = search;
+            self.recount();
+        }
+    }
+
+    fn is_match(&self, entry: &LoggedEvent) -> bool {
+        self.search.as_deref().is_some_and(|search| contains_ignore_case(&entry.event.to_string(), search))
+    }
+
+    fn recount(&mut self) {
+        let shown: Vec<&LoggedEvent> = self.entries.iter().filter(|entry| self.filter.matches(entry)).collect();
+        self.matching = shown.iter().filter(|entry| self.is_match(entry)).count();
+        self.shown = shown.len();
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        self.page = height.max(1);
+
+        // newest first, skip what is scrolled past, stop once the window is full
+        let mut rows = 0;
+        let mut lines: Vec<Line> = Vec::new();
+        for entry in self.entries.iter().rev().filter(|entry| self.filter.matches(entry)).skip(self.scroll) {
+            let line = Line::from(self.highlighted(render_event(entry)));
+            rows += line.width().div_ceil(width).max(1);
+            lines.push(line);
+            if rows >= height {
+                break;
+            }
+        }
+        lines.reverse();
+
+        // the oldest line may only partly fit, the newest always shows
+        let overflow = rows.saturating_sub(height) as u16;
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(self.title()))
+            .wrap(Wrap { trim: false })
+            .scroll((overflow, 0));
+        f.render_widget(paragraph, area);
+    }
+
+    fn title(&self) -> String {
+        let mut title = "Logs".to_owned();
+        if self.follow {
+            title.push_str(" [following]");
+        } else {
+            title.push_str(&format!(" [{} newer below]", self.scroll));
+        }
+        if let Some(kind) = &self.filter.kind {
+            title.push_str(&format!(" kind={}", kind));
+        }
+        if let Some(universe) = &self.filter.universe {
+            title.push_str(&format!(" universe={}", universe));
+        }
+        if let Some(search) = &self.search {
+            title.push_str(&format!(" /{} ({} matching)", search, self.matching));
+        }
+        title
+    }
+
+    /// marks every match of the search, span by span
+    fn highlighted(&self, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+        let Some(search) = self.search.as_deref().filter(|s| !s.is_empty()) else {
+            return spans;
+        };
+        let mark = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+        let mut out = Vec::with_capacity(spans.len());
+        for span in spans {
+            let text = span.content.to_string();
+            let lower = text.to_lowercase();
+            let needle = search.to_lowercase();
+
+            // lowercasing can change byte lengths, leave such spans alone
+            if lower.len() != text.len() {
+                out.push(span);
+                continue;
+            }
+
+            let mut start = 0;
+            while let Some(found) = lower[start..].find(&needle) {
+                let at = start + found;
+                if at > start {
+                    out.push(Span::styled(text[start..at].to_owned(), span.style));
+                }
+                out.push(Span::styled(text[at..at + needle.len()].to_owned(), span.style.patch(mark)));
+                start = at + needle.len();
+            }
+            if start < text.len() {
+                out.push(Span::styled(text[start..].to_owned(), span.style));
+            }
+        }
+        out
+    }
+}
+
+fn contains_ignore_case(text: &str, needle: &str) -> bool {
+    text.to_lowercase().contains(&needle.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use rgb::RGB8;
+    use crate::logging::{SimulationEvent, UniverseRef};
+    use crate::universe::UniverseStatus;
+    use super::*;
+
+    fn universe(name: &str) -> UniverseRef {
+        UniverseRef { id: 1, name: name.to_owned(), color: RGB8::new(255, 255, 255), faction: None }
+    }
+
+    fn info(message: &str) -> LoggedEvent {
+        LoggedEvent::now(SimulationEvent::Info { message: message.to_owned() })
+    }
+
+    fn created(name: &str) -> LoggedEvent {
+        LoggedEvent::now(SimulationEvent::Created { universe: universe(name) })
+    }
+
+    fn status(name: &str) -> LoggedEvent {
+        LoggedEvent::now(SimulationEvent::Status { universe: universe(name), hp: 50, status: UniverseStatus::Running })
+    }
+
+    #[test]
+    fn filter_by_kind_and_universe() {
+        let by_kind = LogFilter { kind: Some("created".to_owned()), universe: None };
+        assert!(by_kind.matches(&created("alpha")));
+        assert!(!by_kind.matches(&info("alpha")));
+
+        let by_universe = LogFilter { kind: None, universe: Some("ALPHA".to_owned()) };
+        assert!(by_universe.matches(&created("alpha")));
+        assert!(!by_universe.matches(&created("beta")));
+        assert!(!by_universe.matches(&info("alpha")));
+    }
+
+    #[test]
+    fn quiet_events_only_when_filtered_for() {
+        assert!(!LogFilter::default().matches(&status("alpha")));
+        assert!(!LogFilter { kind: None, universe: Some("alpha".to_owned()) }.matches(&status("alpha")));
+        assert!(LogFilter { kind: Some("status".to_owned()), universe: None }.matches(&status("alpha")));
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_oldest_shown_event() {
+        let mut logs = LogView::new();
+        for i in 0..25 {
+            logs.push(info(&i.to_string()));
+            logs.push(status("alpha")); // hidden, never scrolled over
+        }
+
+        logs.page_up();
+        assert_eq!(logs.scroll, 10);
+        logs.page_up();
+        logs.page_up();
+        assert_eq!(logs.scroll, 24);
+        logs.home();
+        assert_eq!(logs.scroll, 24);
+
+        logs.page_down();
+        assert_eq!(logs.scroll, 14);
+        assert!(!logs.follow);
+        logs.page_down();
+        logs.page_down();
+        assert_eq!(logs.scroll, 0);
+        assert!(logs.follow);
+    }
+
+    #[test]
+    fn scrolled_back_view_stays_put() {
+        let mut logs = LogView::new();
+        (0..20).for_each(|i| logs.push(info(&i.to_string())));
+        logs.page_up();
+
+        logs.push(info("newer"));
+        logs.push(status("alpha"));
+        assert_eq!(logs.scroll, 11);
+
+        logs.end();
+        logs.push(info("newest"));
+        assert_eq!(logs.scroll, 0);
+    }
+
+    #[test]
+    fn scroll_is_clamped_when_the_oldest_are_evicted() {
+        let mut logs = LogView::new();
+        (0..LOG_CAPACITY).for_each(|i| logs.push(info(&i.to_string())));
+        logs.home();
+        assert_eq!(logs.scroll, LOG_CAPACITY - 1);
+
+        logs.push(info("newer"));
+        assert_eq!(logs.entries.len(), LOG_CAPACITY);
+        assert_eq!(logs.scroll, LOG_CAPACITY - 1);
+    }
+
+    #[test]
+    fn filter_change_follows_again() {
+        let mut logs = LogView::new();
+        (0..20).for_each(|i| logs.push(created(&format!("u{}", i))));
+        logs.page_up();
+
+        logs.set_filter(LogFilter { kind: None, universe: Some("u3".to_owned()) });
+        assert!(logs.follow);
+        assert_eq!(logs.shown, 1);
+        logs.page_up();
+        assert_eq!(logs.scroll, 0);
+    }
+
+    #[test]
+    fn search_matches_are_counted_as_entries_come_and_go() {
+        let mut logs = LogView::new();
+        logs.push(info("alpha strikes"));
+        logs.push(info("beta heals"));
+        logs.push(created("Alpha"));
+
+        logs.set_search(Some("alpha".to_owned()));
+        assert_eq!(logs.matching, 2);
+        assert!(logs.title().ends_with("/alpha (2 matching)"));
+
+        logs.push(info("alpha again"));
+        assert_eq!(logs.matching, 3);
+
+        logs.set_filter(LogFilter { kind: Some("info".to_owned()), universe: None });
+        assert_eq!(logs.matching, 2);
+        logs.set_filter(LogFilter::default());
+
+        // the first two are pushed out of the ring
+        (0..LOG_CAPACITY - 2).for_each(|_| logs.push(info("filler")));
+        assert_eq!(logs.matching, 2);
+        logs.push(info("filler"));
+        assert_eq!(logs.matching, 1);
+
+        logs.set_search(None);
+        assert_eq!(logs.matching, 0);
+        logs.clear();
+        assert_eq!((logs.shown, logs.matching), (0, 0));
+    }
+}
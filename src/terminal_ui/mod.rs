@@ -1,3 +1,11 @@
 ﻿pub mod terminal_ui;
+mod inspector;
+mod log_render;
+mod scoreboard_view;
+mod leaderboard;
+mod overview;
+mod graph_view;
+mod log_view;
+mod input_line;
 
 pub use terminal_ui::TerminalUI;
@@ -0,0 +1,64 @@
+﻿use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Span,
+    widgets::{Block, Borders, Gauge, Paragraph, Sparkline},
+};
+use crate::supervisor::overview::UniverseOverview;
+use crate::terminal_ui::log_render::color;
+use crate::universe::UniverseStatus;
+
+/// rows the overview panel grows to before it stops taking room from the inspector
+pub const OVERVIEW_MAX_ROWS: u16 = 10;
+
+/// one row per universe: name, hp gauge, recent hp, status. all in the universe's colour.
+pub fn render_overview(f: &mut Frame, area: Rect, universes: &[UniverseOverview]) {
+    let block = Block::default().borders(Borders::ALL).title("Universes");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    if universes.is_empty() {
+        let empty = Paragraph::new("no universes yet").style(Style::default().fg(Color::DarkGray));
+        f.render_widget(empty, inner);
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); inner.height as usize])
+        .split(inner);
+
+    for (universe, row) in universes.iter().zip(rows.iter()) {
+        let rgb = color(universe.universe.color);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(12), Constraint::Min(10), Constraint::Length(16), Constraint::Length(11)])
+            .spacing(1)
+            .split(*row);
+
+        f.render_widget(Span::styled(universe.universe.name.clone(), Style::default().fg(rgb)), columns[0]);
+
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(rgb).bg(Color::Black))
+            .ratio(universe.hp.clamp(0, 100) as f64 / 100.0)
+            .label(format!("{}/100", universe.hp.max(0)));
+        f.render_widget(gauge, columns[1]);
+
+        // the most recent ticks that fit
+        let history: Vec<u64> = universe.hp_history.iter().copied().collect();
+        let shown = &history[history.len().saturating_sub(columns[2].width as usize)..];
+        let sparkline = Sparkline::default()
+            .data(shown)
+            .max(100)
+            .style(Style::default().fg(rgb));
+        f.render_widget(sparkline, columns[2]);
+
+        let status_color = match universe.status {
+            UniverseStatus::Running => Color::Green,
+            UniverseStatus::Paused => Color::Yellow,
+            UniverseStatus::Collapsing => Color::Red,
+        };
+        f.render_widget(Span::styled(universe.status.to_string(), Style::default().fg(status_color)), columns[3]);
+    }
+}
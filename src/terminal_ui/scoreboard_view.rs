@@ -0,0 +1,43 @@
+﻿use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+};
+use crate::logging::UniverseScore;
+
+/// draws the scoreboard of the current (or last) match, survivors first
+pub fn render_scoreboard(f: &mut Frame, area: Rect, board: &[UniverseScore]) {
+    let header = Row::new(["universe", "faction", "dealt", "taken", "healed", "kills", "survived"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = board.iter().map(|score| {
+        let style = match score.fell_at {
+            Some(_) => Style::default().fg(Color::DarkGray),
+            None => Style::default(),
+        };
+        Row::new([
+            score.name.clone(),
+            score.faction.clone().unwrap_or_else(|| "-".to_owned()),
+            score.damage_dealt.to_string(),
+            score.damage_taken.to_string(),
+            score.heals_given.to_string(),
+            score.kills.to_string(),
+            score.survival_ticks.to_string(),
+        ]).style(style)
+    });
+
+    let widths = [
+        Constraint::Min(10),
+        Constraint::Length(8),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(5),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Scoreboard"));
+    f.render_widget(table, area);
+}
@@ -1,47 +1,83 @@
 ﻿use ratatui::{
     backend::CrosstermBackend,
-    text::{Span, Line},
     Terminal,
     widgets::{Block, Borders, Paragraph, ListState},
     layout::{Layout, Constraint, Direction},
+    style::Color,
 };
 use crossterm::{
     execute,
-    event::{self, KeyCode, KeyEventKind, Event},
+    event::{self, KeyCode, KeyEventKind, KeyModifiers, Event},
     terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::io::{self, Stdout};
-use tokio::sync::broadcast;
-use crate::logging::subscribe;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use crate::commands::{CommandInterpreter, CommandOutcome, LogFilterChange};
+use crate::logging::{current_tick, subscribe, LoggedEvent, SimulationEvent, UniverseScore};
 use crate::supervisor::log_messages::*;
+use crate::supervisor::combat_stats::{CombatStats, StatsColumn};
+use crate::supervisor::overview::{StatusBoard, UniverseOverview};
+use crate::supervisor::scoreboard::MatchTracker;
+use crate::supervisor::universe_report::UniverseReport;
 use crate::supervisor::user_supervisor::UserSupervisor;
-use crate::universe::{UniverseCommand, UniverseEvent};
+use crate::terminal_ui::inspector::render_inspector;
+use crate::terminal_ui::input_line::InputLine;
+use crate::terminal_ui::log_view::{LogFilter, LogView};
+use crate::terminal_ui::graph_view::{render_graph, EdgeHighlight, RelationshipGraph, HIGHLIGHT_DURATION};
+use crate::terminal_ui::leaderboard::render_leaderboard;
+use crate::terminal_ui::overview::{render_overview, OVERVIEW_MAX_ROWS};
+use crate::terminal_ui::scoreboard_view::render_scoreboard;
+use crate::universe::UniverseId;
 
 pub struct TerminalUI<'a> {
     supervisor: &'a mut UserSupervisor,
-    input: String,
-    logs: Vec<Vec<Span<'static>>>,
-    log_receiver: broadcast::Receiver<Vec<Span<'static>>>,
-    mode: UiMode,
+    input: InputLine,
+    completions: Vec<String>, // what tab could not decide between
+    logs: LogView,
+    searching: bool, // the input box edits the log search
+    log_receiver: broadcast::Receiver<LoggedEvent>,
+    commands: CommandInterpreter,
+    inspected: Option<String>,
+    inspector: Option<UniverseReport>,
+    scoreboard: Option<Vec<UniverseScore>>, // shown instead of the inspector while open
+    tracker: MatchTracker, // rebuilt from the log bus, so a replay fills it just like a live run
+    stats: Vec<CombatStats>,
+    status_board: StatusBoard, // rebuilt from the log bus, like the tracker
+    overview: Vec<UniverseOverview>,
+    graph: RelationshipGraph, // rebuilt from the log bus, like the tracker
+    show_graph: bool,         // the graph instead of the logs
+    highlights: Vec<EdgeHighlight>,
+    stats_sort: StatsColumn,
+    last_inspection: Instant,
 }
 
-#[derive(Clone)]
-enum UiMode {
-    Main,
-    Manage { name: String },
-    EventMenu { name: String },
-}
+const INSPECTOR_REFRESH: Duration = Duration::from_millis(200);
 
 impl<'a> TerminalUI<'a> {
-    pub fn new(supervisor: &'a mut UserSupervisor) -> Self {
+    pub fn new(supervisor: &'a mut UserSupervisor, commands: CommandInterpreter) -> Self {
         let mut log_state = ListState::default();
         log_state.select(Some(0)); // will be updated dynamically
         Self {
             supervisor,
-            input: String::new(),
-            logs: vec![],
+            input: InputLine::new(),
+            completions: Vec::new(),
+            logs: LogView::new(),
+            searching: false,
             log_receiver: subscribe(),
-            mode: UiMode::Main,
+            commands,
+            inspected: None,
+            inspector: None,
+            scoreboard: None,
+            tracker: MatchTracker::default(),
+            stats: Vec::new(),
+            status_board: StatusBoard::default(),
+            overview: Vec::new(),
+            graph: RelationshipGraph::default(),
+            show_graph: false,
+            highlights: Vec::new(),
+            stats_sort: StatsColumn::default(),
+            last_inspection: Instant::now(),
         }
     }
 
@@ -51,22 +87,54 @@ impl<'a> TerminalUI<'a> {
         loop {
             // draw terminal
             self.draw(&mut terminal);
-            if let Some(cmd) = self.poll_input().unwrap() {
-                if self.handle_input(cmd).await {
-                    break;
-                }
+            if let Some(cmd) = self.poll_input().await.unwrap()
+                && self.handle_input(cmd).await {
+                break;
             }
 
             // collect logs for terminal
-            while let Ok(line) = self.log_receiver.try_recv() {
-                self.logs.push(line);
-                if self.logs.len() > 1000 {
-                    self.logs.remove(0);
+            loop {
+                let entry = match self.log_receiver.try_recv() {
+                    Ok(entry) => entry,
+                    Err(TryRecvError::Lagged(missed)) => {
+                        // the panels are built from these events, say that they missed some
+                        let notice = format!("{} events were dropped, the panels may be behind", missed);
+                        self.logs.push(LoggedEvent::now(SimulationEvent::Info { message: notice }));
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                // a replay went back in time, what is on screen did not happen yet
+                if let SimulationEvent::Rewound { .. } = entry.event {
+                    self.logs.clear();
+                }
+                // light up the edge an attack or heal travels on
+                match &entry.event {
+                    SimulationEvent::Attack { source, target, .. } => self.highlight(source.id, target.id, Color::Yellow),
+                    SimulationEvent::Heal { source, target, .. } => self.highlight(source.id, target.id, Color::Cyan),
+                    _ => {}
+                }
+                // the board comes up by itself once a match is over
+                if let SimulationEvent::MatchOver { scoreboard, .. } = &entry.event {
+                    self.close_inspector();
+                    self.scoreboard = Some(scoreboard.clone());
                 }
+                self.tracker.observe(&entry);
+                self.status_board.observe(&entry);
+                self.graph.observe(&entry);
+                self.logs.push(entry);
             }
 
-            // process any incoming universe events (intents)
-            self.supervisor.process_universe_events().await;
+            // the leaderboard and the overview follow every frame
+            self.stats = self.tracker.stats(current_tick());
+            self.status_board.sample_until(current_tick());
+            self.overview = self.status_board.overview();
+            self.highlights.retain(|h| h.at.elapsed() < HIGHLIGHT_DURATION);
+
+            // keep the inspector live
+            if self.last_inspection.elapsed() >= INSPECTOR_REFRESH {
+                self.refresh_inspector().await;
+            }
         }
 
         // end of program, shutdown all universes and clean terminal
@@ -93,200 +161,194 @@ impl<'a> TerminalUI<'a> {
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
                 .split(f.area());
 
-            // Left: commands + input
+            // Left: commands, universe overview, inspector + input
+            let mode_text = self.commands.mode_text();
+            let commands_height = mode_text.lines().count() as u16 + 2;
+            let overview_height = (self.overview.len() as u16).clamp(1, OVERVIEW_MAX_ROWS) + 2;
             let left = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Length(10), Constraint::Min(1), Constraint::Length(3)])
+                .constraints([
+                    Constraint::Length(commands_height),
+                    Constraint::Length(overview_height),
+                    Constraint::Min(1),
+                    Constraint::Length(3),
+                ])
                 .split(chunks[0]);
 
-            let help = Paragraph::new(self.mode_text())
-                .block(Block::default().borders(Borders::ALL).title("Commands"));
+            let title = if self.commands.is_replay() {
+                "Commands (replay)".to_owned()
+            } else {
+                format!("Commands (seed {})", self.supervisor.seed.0)
+            };
+            let help = Paragraph::new(mode_text)
+                .block(Block::default().borders(Borders::ALL).title(title));
             f.render_widget(help, left[0]);
 
-            let input = Paragraph::new(self.input.as_str())
-                .block(Block::default().borders(Borders::ALL).title("Input"));
-            f.render_widget(input, left[2]);
-
-            // Right: logs
-            let log_lines: Vec<Line> = self.logs.iter()
-                .map(|spans| Line::from(spans.clone()))
-                .collect();
-
-            // Calculate how much to scroll to show bottom
-            let area = chunks[1];
-            let inner_height = area.height.saturating_sub(2) as usize; // minus border
-            let total_lines = log_lines.len();
-            let scroll_offset = if total_lines > inner_height {
-                (total_lines - inner_height) as u16
+            render_overview(f, left[1], &self.overview);
+
+            match &self.scoreboard {
+                Some(board) => render_scoreboard(f, left[2], board),
+                None => render_inspector(f, left[2], self.inspector.as_ref()),
+            }
+
+            let input_title = if self.searching {
+                "Search logs (Enter keeps, Esc clears)".to_owned()
+            } else if self.completions.is_empty() {
+                "Input".to_owned()
             } else {
-                0
+                format!("Input: {}", self.completions.join(" "))
             };
+            self.input.render(f, left[3], &input_title);
 
-            let logs_paragraph = Paragraph::new(log_lines)
-                .block(Block::default().borders(Borders::ALL).title("Logs"))
-                .wrap(ratatui::widgets::Wrap { trim: false })
-                .scroll((scroll_offset, 0));
+            // Right: logs and the leaderboard beside them
+            let right = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(30), Constraint::Length(48)])
+                .split(chunks[1]);
+            render_leaderboard(f, right[1], &mut self.stats, self.stats_sort);
 
-            f.render_widget(logs_paragraph, chunks[1]);
+            if self.show_graph {
+                render_graph(f, right[0], &self.graph, &self.highlights);
+                return;
+            }
+
+            self.logs.render(f, right[0]);
         });
     }
 
-    fn poll_input(&mut self) -> io::Result<Option<String>> {
-        if !event::poll(std::time::Duration::from_millis(16))? {
-            return Ok(None);
-        }
-
-        match event::read()? {
-            Event::Key(k) if k.kind == KeyEventKind::Press => match k.code {
-                KeyCode::Enter => {
-                    let cmd = self.input.clone();
-                    self.input.clear();
-                    Ok(Some(cmd))
-                }
-                KeyCode::Char(c) => {
-                    self.input.push(c);
-                    Ok(None)
-                }
-                KeyCode::Backspace => {
-                    self.input.pop();
-                    Ok(None)
+    /// runs the command, returns true when the user asked to shut down
+    async fn handle_input(&mut self, line: String) -> bool {
+        match self.commands.execute(&line).await {
+            CommandOutcome::Continue => {}
+            CommandOutcome::Inspect(name) => {
+                self.scoreboard = None;
+                self.inspected = Some(name);
+                self.refresh_inspector().await;
+            }
+            CommandOutcome::CloseInspector => {
+                self.close_inspector();
+                self.scoreboard = None;
+            }
+            CommandOutcome::Leaderboard(column) => self.stats_sort = column,
+            CommandOutcome::FilterLogs(change) => {
+                let mut filter = self.logs.filter().clone();
+                match change {
+                    LogFilterChange::Kind(kind) => filter.kind = Some(kind),
+                    LogFilterChange::Universe(name) => filter.universe = Some(name),
+                    LogFilterChange::Off => filter = LogFilter::default(),
                 }
-                _ => Ok(None),
-            },
-            _ => Ok(None),
+                self.logs.set_filter(filter);
+            }
+            CommandOutcome::ToggleGraph => {
+                self.show_graph = !self.show_graph;
+                self.highlights.clear();
+            }
+            CommandOutcome::Scoreboard => {
+                self.close_inspector();
+                self.scoreboard = Some(self.supervisor.client.scoreboard().await);
+            }
+            CommandOutcome::Shutdown => return true,
         }
+        false
     }
 
-    fn mode_text(&self) -> String {
-        match &self.mode {
-            UiMode::Main => "new <name>\nlist\nmanage <name>\nshutdown".into(),
-            UiMode::Manage { name } => format!(
-                "Managing '{}':\nresume\npause\nevent\nstate\ncollapse\nback",
-                name
-            ),
-            UiMode::EventMenu { name } => format!(
-                "Event on '{}':\nshatter\ncrash\nheal\nping\npong\nback",
-                name
-            ),
-        }
-    }
+    /// re-queries the inspected universe, closes the inspector if it is gone
+    async fn refresh_inspector(&mut self) {
+        self.last_inspection = Instant::now();
 
-    async fn handle_input(&mut self, line: String) -> bool {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
-            return false;
+        if self.scoreboard.is_some() {
+            self.scoreboard = Some(self.supervisor.client.scoreboard().await);
         }
 
-        let mode = &self.mode;
+        let Some(name) = self.inspected.clone() else {
+            return;
+        };
 
-        match mode {
-            UiMode::Main => self.handle_main(parts).await,
-            UiMode::Manage { name } => self.handle_manage(name.clone(), parts).await,
-            UiMode::EventMenu { name } => self.handle_user_event(name.clone(), parts).await,
-        }
-    }
-
-    async fn handle_main(&mut self, p: Vec<&str>) -> bool {
-        let input = p.join(" "); // reconstruct full line
-        let parts: Vec<&str> = input.splitn(2, ' ').collect(); // only split once
-
-        match parts[0].to_lowercase().as_str() {
-            "new" => {
-                if parts.len() > 1 {
-                    let name = parts[1].trim().to_string();
-                    if name.is_empty() {
-                        Log::info("Name cannot be empty");
-                    } else {
-                        self.supervisor.new_universe(name.clone()).await;
-                    }
-                } else {
-                    Log::info("Usage: new <name>");
-                }
-            }
-            "manage" => {
-                if parts.len() > 1 {
-                    let name = parts[1].trim().to_string();
-                    
-                    if !self.supervisor.supervisor.does_universe_exist(name.clone()) {
-                        Log::info(format!("'{}' not found", name));
-                        return false;
-                    }
-                    
-                    Log::info(format!("Now managing '{}'", name));
-                    self.mode = UiMode::Manage { name };
-                } else {
-                    Log::info("Usage: manage <name>");
-                }
+        match self.supervisor.client.query_universe_state(name).await {
+            Ok(report) => self.inspector = Some(report),
+            Err(e) => {
+                Log::info(format!("Inspector closed: {}", e));
+                self.close_inspector();
             }
-            "list" => {
-                let list = self.supervisor.get_list_universes();
-                Log::info(format!("Universes: {:?}", list));
-            }
-            "shutdown" => return true,
-            _ => Log::info(format!("Unknown command: '{}'", parts[0]))
         }
-        false
     }
 
-    async fn handle_manage(&mut self, name: String, p: Vec<&str>) -> bool {
-        match p[0].to_lowercase().as_str() {
-            "back" => self.mode = UiMode::Main,
-            "resume" => {
-                Log::info(format!("Resuming {}", name));
-                self.supervisor.supervisor.send_universe_command(name.clone(), UniverseCommand::Start).await;
-            }
-            "pause" => {
-                Log::info(format!("Pausing {}", name));
-                self.supervisor.supervisor.send_universe_command(name.clone(), UniverseCommand::Stop).await;
-            }
-            "event" => self.mode = UiMode::EventMenu { name },
-            "collapse" => {
-                Log::info(format!("Collapsing {}", name));
-                self.supervisor.supervisor.send_universe_command(name.clone(), UniverseCommand::Shutdown).await;
-            }
-            _ => Log::info("Unknown manage command"),
+    fn highlight(&mut self, source: UniverseId, target: UniverseId, color: Color) {
+        if self.show_graph {
+            self.highlights.push(EdgeHighlight { source, target, color, at: Instant::now() });
         }
-        false
     }
 
-    async fn handle_user_event(&mut self, name: String, p: Vec<&str>) -> bool {
-        // get user handle for logs color
-        let universe_color = self.supervisor.supervisor.existing_universes
-            .get(&self.supervisor.supervisor.universes_via_name[&name])
-            .unwrap().color;
+    fn close_inspector(&mut self) {
+        self.inspected = None;
+        self.inspector = None;
+    }
 
-        match p[0].to_lowercase().as_str() {
-            "back" => self.mode = UiMode::Manage { name },
-            "shatter" => {
-                let strength = 20;
+    async fn poll_input(&mut self) -> io::Result<Option<String>> {
+        if !event::poll(std::time::Duration::from_millis(16))? {
+            return Ok(None);
+        }
 
-                Log::user_action("You", "shattered", &name, universe_color);
+        let event = event::read()?;
+        if let Event::Key(k) = &event
+            && k.kind == KeyEventKind::Press {
+            self.completions.clear();
+        }
 
-                self.supervisor.supervisor.send_universe_command(
-                    name.clone(),
-                    UniverseCommand::InjectEvent(UniverseEvent::Shatter(strength)),
-                ).await;
-            }
-            "heal" => {
-                let strength = 20;
+        match event {
+            Event::Key(k) if k.kind == KeyEventKind::Press => match k.code {
+                // the log panel
+                KeyCode::PageUp => self.logs.page_up(),
+                KeyCode::PageDown => self.logs.page_down(),
+                KeyCode::Home if k.modifiers.contains(KeyModifiers::CONTROL) => self.logs.home(),
+                KeyCode::End if k.modifiers.contains(KeyModifiers::CONTROL) => self.logs.end(),
+                KeyCode::Char('f') if k.modifiers.contains(KeyModifiers::CONTROL) => self.logs.toggle_follow(),
+
+                // line editing
+                KeyCode::Left => self.input.left(),
+                KeyCode::Right => self.input.right(),
+                KeyCode::Home => self.input.home(),
+                KeyCode::End => self.input.end(),
+                KeyCode::Char('w') if k.modifiers.contains(KeyModifiers::CONTROL) => self.input.delete_word(),
+                KeyCode::Up if !self.searching => self.input.history_up(),
+                KeyCode::Down if !self.searching => self.input.history_down(),
+                KeyCode::Tab if !self.searching => {
+                    let candidates = if self.input.completing_command() {
+                        self.commands.command_names()
+                    } else {
+                        self.supervisor.client.list_universes().await
+                    };
+                    self.completions = self.input.complete(&candidates);
+                }
 
-                Log::user_action("You", "healed", &name, universe_color);
+                // `/` on an empty line searches the logs
+                KeyCode::Char('/') if self.input.is_empty() && !self.searching => self.searching = true,
+                KeyCode::Esc => {
+                    if self.searching {
+                        self.searching = false;
+                        self.input.clear();
+                    }
+                    self.logs.set_search(None);
+                }
+                KeyCode::Enter if self.searching => {
+                    self.searching = false;
+                    self.logs.set_search(Some(self.input.take()).filter(|s| !s.is_empty()));
+                }
 
-                self.supervisor.supervisor.send_universe_command(
-                    name.clone(),
-                    UniverseCommand::InjectEvent(UniverseEvent::Heal(strength)),
-                ).await;
-            }
-            "crash" => {
-                Log::user_action("You", "CRASHED", &name, universe_color);
+                KeyCode::Enter => {
+                    return Ok(Some(self.input.submit()));
+                }
+                KeyCode::Char(c) => self.input.insert(c),
+                KeyCode::Backspace => self.input.backspace(),
+                _ => {}
+            },
+            _ => {}
+        }
 
-                self.supervisor.supervisor.send_universe_command(
-                    name.clone(),
-                    UniverseCommand::InjectEvent(UniverseEvent::Crash),
-                ).await;
-            }
-            _ => Log::info("Unknown event command"),
+        // searching as you type
+        if self.searching {
+            self.logs.set_search(Some(self.input.as_str().to_owned()));
         }
-        false
+        Ok(None)
     }
 }
@@ -0,0 +1,50 @@
+﻿use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::universe::{Relationship, UniverseId, UniverseIntent};
+
+/// ways a universe can try to change what it is to another one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiplomaticAction {
+    DeclareWar, // needs nobody's consent
+    Ally,
+    Peace,
+}
+
+impl DiplomaticAction {
+    /// the affinity the pair ends up with once it goes through
+    pub fn affinity(&self) -> i32 {
+        match self {
+            DiplomaticAction::DeclareWar => -80,
+            DiplomaticAction::Ally => 40,
+            DiplomaticAction::Peace => 0,
+        }
+    }
+
+    /// nothing left to negotiate, they already are what is proposed
+    pub fn is_settled(&self, relationship: Relationship) -> bool {
+        match self {
+            DiplomaticAction::DeclareWar => relationship == Relationship::Enemy,
+            DiplomaticAction::Ally => relationship.is_friendly(),
+            DiplomaticAction::Peace => relationship == Relationship::Neutral,
+        }
+    }
+
+    pub fn intent(&self, target: UniverseId) -> UniverseIntent {
+        match self {
+            DiplomaticAction::DeclareWar => UniverseIntent::DeclareWar { target },
+            DiplomaticAction::Ally => UniverseIntent::ProposeAlliance { target },
+            DiplomaticAction::Peace => UniverseIntent::ProposePeace { target },
+        }
+    }
+}
+
+impl fmt::Display for DiplomaticAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiplomaticAction::DeclareWar => write!(f, "war declaration"),
+            DiplomaticAction::Ally => write!(f, "alliance proposal"),
+            DiplomaticAction::Peace => write!(f, "peace proposal"),
+        }
+    }
+}
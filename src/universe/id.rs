@@ -6,3 +6,13 @@ static NEXT_UNIVERSE_ID: AtomicUsize = AtomicUsize::new(1);
 pub fn new_universe_id() -> UniverseId {
     NEXT_UNIVERSE_ID.fetch_add(1, Ordering::Relaxed)
 }
+
+/// the id the next universe will get, without taking it
+pub fn peek_next_universe_id() -> UniverseId {
+    NEXT_UNIVERSE_ID.load(Ordering::Relaxed)
+}
+
+/// only for restoring a saved multiverse, ids handed out before must not be reused
+pub fn set_next_universe_id(next: UniverseId) {
+    NEXT_UNIVERSE_ID.store(next, Ordering::Relaxed);
+}
@@ -1,9 +1,17 @@
 ﻿use crate::universe::id::UniverseId;
 
+/// strongest attack or heal a universe may dish out at once
+pub const MAX_STRENGTH: i32 = 20;
+
 // events that conclude the result of a universe action, sent to himself.
 #[derive(Debug, Clone)]
 pub enum UniverseIntent {
     Attack { target: UniverseId, damage: i32 },
     Heal   { target: UniverseId, amount: i32 },
     Dead   { target: UniverseId, },
+    Ping   { target: UniverseId },              // supervisor times the round trip
+    Pong   { target: UniverseId, nonce: u64 },  // answer to a ping, target is the pinger
+    DeclareWar      { target: UniverseId },
+    ProposeAlliance { target: UniverseId },     // the target decides whether to accept
+    ProposePeace    { target: UniverseId },     // the target decides whether to accept
 }
\ No newline at end of file
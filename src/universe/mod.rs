@@ -5,10 +5,23 @@ pub mod universe_handle;
 mod universe;
 mod relationship;
 mod intent;
+mod targeting;
+mod options;
+mod world_view;
+mod script;
+mod diplomacy;
+mod status;
 
-pub use id::{UniverseId, new_universe_id};
-pub use universe_handle::{UniverseHandle, create_universe_handle};
+pub use id::{UniverseId, new_universe_id, peek_next_universe_id, set_next_universe_id};
+pub use universe_handle::{UniverseHandle, create_universe_handle, restore_universe_handle};
 pub use universe_command::UniverseCommand;
 pub use universe_event::UniverseEvent;
-pub use relationship::Relationship;
-pub use intent::UniverseIntent;
+pub use relationship::{Relationship, AFFINITY_RANGE};
+pub use intent::{UniverseIntent, MAX_STRENGTH};
+pub use universe::UniverseSnapshot;
+pub use targeting::{TargetingKind, TargetingStrategy, TargetingView};
+pub use options::UniverseOptions;
+pub use world_view::{NeighbourStatus, NeighbourView, WorldView};
+pub use script::UniverseScript;
+pub use diplomacy::DiplomaticAction;
+pub use status::{StatusReport, UniverseStatus};
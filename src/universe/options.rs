@@ -0,0 +1,10 @@
+﻿use std::path::PathBuf;
+use crate::universe::TargetingKind;
+
+/// how a new universe behaves, picked by the user when creating it
+#[derive(Debug, Clone, Default)]
+pub struct UniverseOptions {
+    pub targeting: TargetingKind,
+    pub script: Option<PathBuf>, // tactics script, takes over from the targeting strategy
+    pub faction: Option<String>, // team it fights for, relationships follow from it
+}
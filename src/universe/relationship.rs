@@ -1,5 +1,61 @@
-﻿#[derive(Clone, Copy, Debug)]
+﻿use std::ops::RangeInclusive;
+use serde::{Deserialize, Serialize};
+
+/// how much two universes like each other, from sworn enemies to brothers
+pub const AFFINITY_RANGE: RangeInclusive<i32> = -100..=100;
+
+/// what an affinity score means. hostile ones fight, friendly ones heal each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Relationship {
     Enemy,
+    Rival,
+    Neutral,
+    Ally,
     Brother,
-}
\ No newline at end of file
+}
+
+impl Relationship {
+    pub fn from_affinity(affinity: i32) -> Relationship {
+        match affinity {
+            ..=-60 => Relationship::Enemy,
+            -59..=-20 => Relationship::Rival,
+            -19..=19 => Relationship::Neutral,
+            20..=59 => Relationship::Ally,
+            60.. => Relationship::Brother,
+        }
+    }
+
+    /// may attack each other
+    pub fn is_hostile(&self) -> bool {
+        matches!(self, Relationship::Enemy | Relationship::Rival)
+    }
+
+    /// may heal each other
+    pub fn is_friendly(&self) -> bool {
+        matches!(self, Relationship::Ally | Relationship::Brother)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn affinity_bands() {
+        for (affinity, relationship) in [
+            (-100, Relationship::Enemy),
+            (-60, Relationship::Enemy),
+            (-59, Relationship::Rival),
+            (-20, Relationship::Rival),
+            (-19, Relationship::Neutral),
+            (0, Relationship::Neutral),
+            (19, Relationship::Neutral),
+            (20, Relationship::Ally),
+            (59, Relationship::Ally),
+            (60, Relationship::Brother),
+            (100, Relationship::Brother),
+        ] {
+            assert_eq!(Relationship::from_affinity(affinity), relationship, "at {}", affinity);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+﻿use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST, INT};
+use crate::supervisor::log_messages::Log;
+use crate::universe::{DiplomaticAction, UniverseId};
+/// intents a script may return per step, the rest are dropped
+pub const MAX_SCRIPT_INTENTS: usize = 2;
+
+/// a compiled tactics script, shared by the universes running it.
+/// it must define `fn step(state)` returning an array of `attack(..)`, `heal(..)`, `ping(..)`
+/// and the diplomacy calls `declare_war(..)`, `ally(..)` and `peace(..)`.
+#[derive(Debug, Clone)]
+pub struct UniverseScript {
+    pub path: PathBuf,
+    ast: AST,
+}
+
+impl UniverseScript {
+    pub async fn load(path: &Path) -> Result<UniverseScript, String> {
+        let source = tokio::fs::read_to_string(path).await
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let ast = Engine::new().compile(source)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if !ast.iter_functions().any(|f| f.name == "step" && f.params.len() == 1) {
+            return Err(format!("{}: no `fn step(state)` defined", path.display()));
+        }
+
+        Ok(UniverseScript { path: path.to_path_buf(), ast })
+    }
+}
+
+/// what the script gets to see, a copy: changing it changes nothing
+pub struct ScriptState<'a> {
+    pub id: UniverseId,
+    pub hp: i32,
+    pub tick: i32,
+    pub enemies: &'a BTreeSet<UniverseId>,
+    pub brothers: &'a BTreeSet<UniverseId>,
+    pub known_hp: &'a BTreeMap<UniverseId, i32>,
+    pub last_attacker: Option<UniverseId>,
+    pub strength: i32, // a fresh roll in the built-in range, scripts have no rng of their own
+    pub roll: i32,     // 0..100, same
+}
+
+impl ScriptState<'_> {
+    fn to_map(&self) -> Map {
+        let ids = |set: &BTreeSet<UniverseId>| -> Array {
+            set.iter().map(|&id| Dynamic::from(id as INT)).collect()
+        };
+        let known_hp: Map = self.known_hp.iter()
+            .map(|(id, hp)| (id.to_string().into(), Dynamic::from(*hp as INT)))
+            .collect();
+
+        let mut state = Map::new();
+        state.insert("id".into(), Dynamic::from(self.id as INT));
+        state.insert("hp".into(), Dynamic::from(self.hp as INT));
+        state.insert("tick".into(), Dynamic::from(self.tick as INT));
+        state.insert("enemies".into(), Dynamic::from_array(ids(self.enemies)));
+        state.insert("brothers".into(), Dynamic::from_array(ids(self.brothers)));
+        state.insert("known_hp".into(), Dynamic::from_map(known_hp));
+        state.insert("last_attacker".into(), self.last_attacker.map_or(Dynamic::UNIT, |id| Dynamic::from(id as INT)));
+        state.insert("strength".into(), Dynamic::from(self.strength as INT));
+        state.insert("roll".into(), Dynamic::from(self.roll as INT));
+        state
+    }
+}
+
+/// what a script asked for, not checked yet
+#[derive(Debug)]
+pub enum ScriptIntent {
+    Attack { target: INT, damage: INT },
+    Heal { target: INT, amount: INT },
+    Ping { target: INT },
+    Diplomacy { target: INT, action: DiplomaticAction },
+}
+
+/// a script bound to one universe, with its own engine
+pub struct ScriptBrain {
+    pub script: UniverseScript,
+    engine: Engine,
+}
+
+impl ScriptBrain {
+    pub fn new(id: UniverseId, script: UniverseScript) -> ScriptBrain {
+        let mut engine = Engine::new();
+
+        // a step has to fit in a tick, endless loops are cut short
+        engine.set_max_operations(50_000);
+        engine.set_max_call_levels(32);
+        engine.set_max_array_size(256);
+        engine.set_max_map_size(256);
+        engine.set_max_string_size(1024);
+
+        engine.on_print(move |text| Log::info(format!("script of #{}: {}", id, text)));
+        engine.register_fn("attack", |target: INT, damage: INT| intent_map("attack", target, damage));
+        engine.register_fn("heal", |target: INT, amount: INT| intent_map("heal", target, amount));
+        engine.register_fn("ping", |target: INT| intent_map("ping", target, 0));
+        engine.register_fn("declare_war", |target: INT| intent_map("declare_war", target, 0));
+        engine.register_fn("ally", |target: INT| intent_map("ally", target, 0));
+        engine.register_fn("peace", |target: INT| intent_map("peace", target, 0));
+
+        ScriptBrain { script, engine }
+    }
+
+    /// runs the script's `step`, returns the intents it asked for
+    pub fn decide(&self, state: &ScriptState) -> Result<Vec<ScriptIntent>, String> {
+        let result: Array = self.engine
+            .call_fn(&mut Scope::new(), &self.script.ast, "step", (Dynamic::from_map(state.to_map()),))
+            .map_err(|e| e.to_string())?;
+
+        result.into_iter().map(parse_intent).collect()
+    }
+}
+
+fn intent_map(kind: &str, target: INT, amount: INT) -> Map {
+    let mut intent = Map::new();
+    intent.insert("kind".into(), kind.into());
+    intent.insert("target".into(), Dynamic::from(target));
+    intent.insert("amount".into(), Dynamic::from(amount));
+    intent
+}
+
+fn parse_intent(value: Dynamic) -> Result<ScriptIntent, String> {
+    let map = value.try_cast::<Map>()
+        .ok_or("step must return intents made with attack(), heal(), ping() or a diplomacy call")?;
+
+    let int = |key: &str| map.get(key).and_then(|v| v.as_int().ok())
+        .ok_or_else(|| format!("intent without a numeric '{}'", key));
+    let kind = map.get("kind").and_then(|v| v.clone().into_string().ok())
+        .ok_or("intent without a kind")?;
+
+    match kind.as_str() {
+        "attack" => Ok(ScriptIntent::Attack { target: int("target")?, damage: int("amount")? }),
+        "heal" => Ok(ScriptIntent::Heal { target: int("target")?, amount: int("amount")? }),
+        "ping" => Ok(ScriptIntent::Ping { target: int("target")? }),
+        "declare_war" => Ok(ScriptIntent::Diplomacy { target: int("target")?, action: DiplomaticAction::DeclareWar }),
+        "ally" => Ok(ScriptIntent::Diplomacy { target: int("target")?, action: DiplomaticAction::Ally }),
+        "peace" => Ok(ScriptIntent::Diplomacy { target: int("target")?, action: DiplomaticAction::Peace }),
+        other => Err(format!("unknown intent '{}'", other)),
+    }
+}
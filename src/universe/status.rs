@@ -0,0 +1,28 @@
+﻿use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// what a universe is up to, as far as its own task knows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UniverseStatus {
+    Running,
+    Paused,
+    Collapsing, // out of hp or shut down, about to be gone
+}
+
+impl fmt::Display for UniverseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniverseStatus::Running => write!(f, "running"),
+            UniverseStatus::Paused => write!(f, "paused"),
+            UniverseStatus::Collapsing => write!(f, "collapsing"),
+        }
+    }
+}
+
+/// sent back after every step
+#[derive(Debug, Clone, Copy)]
+pub struct StatusReport {
+    pub hp: i32,
+    pub status: UniverseStatus,
+}
@@ -0,0 +1,285 @@
+﻿use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use crate::universe::UniverseId;
+
+/// what a universe knows when it picks whom to attack
+pub struct TargetingView<'a> {
+    pub enemies: &'a BTreeSet<UniverseId>,
+    pub known_hp: &'a BTreeMap<UniverseId, i32>, // as far as this universe can tell
+    pub last_attacker: Option<UniverseId>,
+}
+
+impl TargetingView<'_> {
+    fn hp_of(&self, id: UniverseId) -> i32 {
+        self.known_hp.get(&id).copied().unwrap_or(100)
+    }
+
+    fn random_enemy(&self, rng: &mut StdRng) -> Option<UniverseId> {
+        if self.enemies.is_empty() {
+            return None;
+        }
+        self.enemies.iter().nth(rng.random_range(0..self.enemies.len())).copied()
+    }
+}
+
+/// picks the enemy a universe attacks next. may keep state between picks.
+pub trait TargetingStrategy: Send {
+    fn kind(&self) -> TargetingKind;
+    fn pick(&mut self, view: &TargetingView, rng: &mut StdRng) -> Option<UniverseId>;
+}
+
+/// the strategies to choose from, by name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetingKind {
+    #[default]
+    Random,
+    Weakest,
+    Strongest,
+    RoundRobin,
+    FocusFire,
+    Revenge,
+}
+
+impl TargetingKind {
+    pub const ALL: [TargetingKind; 6] = [
+        TargetingKind::Random,
+        TargetingKind::Weakest,
+        TargetingKind::Strongest,
+        TargetingKind::RoundRobin,
+        TargetingKind::FocusFire,
+        TargetingKind::Revenge,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetingKind::Random => "random",
+            TargetingKind::Weakest => "weakest",
+            TargetingKind::Strongest => "strongest",
+            TargetingKind::RoundRobin => "round-robin",
+            TargetingKind::FocusFire => "focus-fire",
+            TargetingKind::Revenge => "revenge",
+        }
+    }
+
+    pub fn strategy(&self) -> Box<dyn TargetingStrategy> {
+        match self {
+            TargetingKind::Random => Box::new(RandomTarget),
+            TargetingKind::Weakest => Box::new(WeakestEnemy),
+            TargetingKind::Strongest => Box::new(StrongestEnemy),
+            TargetingKind::RoundRobin => Box::new(RoundRobin { last: None }),
+            TargetingKind::FocusFire => Box::new(FocusFire { target: None }),
+            TargetingKind::Revenge => Box::new(Revenge),
+        }
+    }
+}
+
+impl FromStr for TargetingKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TargetingKind::ALL.into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = TargetingKind::ALL.iter().map(|kind| kind.name()).collect();
+                format!("'{}' is not a targeting strategy ({})", s, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for TargetingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// any enemy, rolled every time
+struct RandomTarget;
+
+impl TargetingStrategy for RandomTarget {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::Random
+    }
+
+    fn pick(&mut self, view: &TargetingView, rng: &mut StdRng) -> Option<UniverseId> {
+        view.random_enemy(rng)
+    }
+}
+
+/// the enemy closest to collapsing, finish it off
+struct WeakestEnemy;
+
+impl TargetingStrategy for WeakestEnemy {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::Weakest
+    }
+
+    fn pick(&mut self, view: &TargetingView, _rng: &mut StdRng) -> Option<UniverseId> {
+        view.enemies.iter().copied().min_by_key(|&id| view.hp_of(id))
+    }
+}
+
+/// the biggest threat first
+struct StrongestEnemy;
+
+impl TargetingStrategy for StrongestEnemy {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::Strongest
+    }
+
+    fn pick(&mut self, view: &TargetingView, _rng: &mut StdRng) -> Option<UniverseId> {
+        view.enemies.iter().copied().max_by_key(|&id| view.hp_of(id))
+    }
+}
+
+/// every enemy in turn, by id
+struct RoundRobin {
+    last: Option<UniverseId>,
+}
+
+impl TargetingStrategy for RoundRobin {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::RoundRobin
+    }
+
+    fn pick(&mut self, view: &TargetingView, _rng: &mut StdRng) -> Option<UniverseId> {
+        let after = self.last.map_or(Bound::Unbounded, Bound::Excluded);
+        let next = view.enemies.range((after, Bound::Unbounded)).next()
+            .or_else(|| view.enemies.iter().next())
+            .copied();
+
+        self.last = next;
+        next
+    }
+}
+
+/// sticks to one enemy until it is gone, then rolls a new one
+struct FocusFire {
+    target: Option<UniverseId>,
+}
+
+impl TargetingStrategy for FocusFire {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::FocusFire
+    }
+
+    fn pick(&mut self, view: &TargetingView, rng: &mut StdRng) -> Option<UniverseId> {
+        if !self.target.is_some_and(|target| view.enemies.contains(&target)) {
+            self.target = view.random_enemy(rng);
+        }
+        self.target
+    }
+}
+
+/// hits back at whoever hit last, anyone while nobody did
+struct Revenge;
+
+impl TargetingStrategy for Revenge {
+    fn kind(&self) -> TargetingKind {
+        TargetingKind::Revenge
+    }
+
+    fn pick(&mut self, view: &TargetingView, rng: &mut StdRng) -> Option<UniverseId> {
+        view.last_attacker
+            .filter(|attacker| view.enemies.contains(attacker))
+            .or_else(|| view.random_enemy(rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+
+    /// enemies 2, 3 and 5 at 60, 20 and 90 hp
+    fn known() -> (BTreeSet<UniverseId>, BTreeMap<UniverseId, i32>) {
+        (BTreeSet::from([2, 3, 5]), BTreeMap::from([(2, 60), (3, 20), (5, 90)]))
+    }
+
+    fn picks(kind: TargetingKind, view: &TargetingView, times: usize) -> Vec<Option<UniverseId>> {
+        let mut strategy = kind.strategy();
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..times).map(|_| strategy.pick(view, &mut rng)).collect()
+    }
+
+    #[test]
+    fn weakest_and_strongest_go_by_known_hp() {
+        let (enemies, known_hp) = known();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: None };
+
+        assert_eq!(picks(TargetingKind::Weakest, &view, 1), [Some(3)]);
+        assert_eq!(picks(TargetingKind::Strongest, &view, 1), [Some(5)]);
+    }
+
+    #[test]
+    fn unseen_enemies_count_as_full_hp() {
+        let (enemies, _) = known();
+        let known_hp = BTreeMap::from([(2, 100), (3, 100), (5, 40)]);
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: None };
+        assert_eq!(picks(TargetingKind::Weakest, &view, 1), [Some(5)]);
+
+        let blind = BTreeMap::from([(3, 99)]);
+        let view = TargetingView { enemies: &enemies, known_hp: &blind, last_attacker: None };
+        assert_eq!(picks(TargetingKind::Weakest, &view, 1), [Some(3)]);
+    }
+
+    #[test]
+    fn round_robin_takes_turns_by_id() {
+        let (enemies, known_hp) = known();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: None };
+        assert_eq!(picks(TargetingKind::RoundRobin, &view, 4), [Some(2), Some(3), Some(5), Some(2)]);
+    }
+
+    #[test]
+    fn focus_fire_sticks_to_one_enemy() {
+        let (enemies, known_hp) = known();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: None };
+        let picked = picks(TargetingKind::FocusFire, &view, 5);
+        assert!(picked[0].is_some_and(|target| enemies.contains(&target)));
+        assert!(picked.iter().all(|&pick| pick == picked[0]));
+    }
+
+    #[test]
+    fn revenge_hits_back_at_an_enemy() {
+        let (enemies, known_hp) = known();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: Some(5) };
+        assert_eq!(picks(TargetingKind::Revenge, &view, 2), [Some(5), Some(5)]);
+
+        // no longer an enemy, anyone will do
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: Some(9) };
+        assert!(picks(TargetingKind::Revenge, &view, 1)[0].is_some_and(|target| enemies.contains(&target)));
+    }
+
+    #[test]
+    fn same_seed_same_random_picks() {
+        let (enemies, known_hp) = known();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: None };
+        let picked = picks(TargetingKind::Random, &view, 20);
+        assert_eq!(picked, picks(TargetingKind::Random, &view, 20));
+        assert!(picked.iter().all(|pick| pick.is_some_and(|target| enemies.contains(&target))));
+    }
+
+    #[test]
+    fn no_enemies_no_target() {
+        let enemies = BTreeSet::new();
+        let known_hp = BTreeMap::new();
+        let view = TargetingView { enemies: &enemies, known_hp: &known_hp, last_attacker: Some(2) };
+        for kind in TargetingKind::ALL {
+            assert_eq!(picks(kind, &view, 2), [None, None], "{} found a target", kind);
+        }
+    }
+
+    #[test]
+    fn names_parse_back() {
+        for kind in TargetingKind::ALL {
+            assert_eq!(kind.name().parse::<TargetingKind>(), Ok(kind));
+            assert_eq!(kind.strategy().kind(), kind);
+        }
+        assert!("sniper".parse::<TargetingKind>().is_err());
+    }
+}
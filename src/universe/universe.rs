@@ -1,7 +1,11 @@
-﻿use std::collections::HashSet;
-use rand::{rng, Rng};
+﻿use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use rand::Rng;
+use rand::rngs::StdRng;
 use tokio::sync::mpsc::UnboundedSender;
-use crate::universe::{new_universe_id, UniverseId, UniverseIntent};
+use crate::supervisor::log_messages::Log;
+use crate::universe::script::{ScriptBrain, ScriptIntent, ScriptState, MAX_SCRIPT_INTENTS};
+use crate::universe::{DiplomaticAction, Relationship, StatusReport, UniverseStatus, TargetingKind, TargetingStrategy, TargetingView, UniverseId, UniverseIntent, UniverseScript, WorldView, MAX_STRENGTH};
 
 pub struct Universe {
     pub(crate) id: UniverseId,
@@ -10,14 +14,34 @@ pub struct Universe {
     pub(crate) collapsed: bool,
     pub(crate) tick: i32,
     pub(crate) hp: i32,
-    pub(crate) enemies: HashSet<UniverseId>,
-    pub(crate) brothers: HashSet<UniverseId>,
+    // ordered sets, iteration order must not depend on the process for seeded runs
+    pub(crate) enemies: BTreeSet<UniverseId>,
+    pub(crate) brothers: BTreeSet<UniverseId>,
+    rng: StdRng, // seeded from the simulation seed + own id
+    targeting: Box<dyn TargetingStrategy>,
+    known_hp: BTreeMap<UniverseId, i32>, // others' hp, from the last world view and own blows since
+    world: Option<WorldView>,            // the last world view, only the universes we relate to
+    last_attacker: Option<UniverseId>,
+    script: Option<ScriptBrain>, // replaces the built-in tactics when set
 }
 
-impl Universe {
-    pub(crate) fn new(intent_tx: UnboundedSender<UniverseIntent>) -> Universe {
-        let id = new_universe_id();
+/// point in time copy of a universe's state, sent back to whoever asked for it.
+#[derive(Debug, Clone)]
+pub struct UniverseSnapshot {
+    pub id: UniverseId,
+    pub hp: i32,
+    pub tick: i32,
+    pub executes: bool,
+    pub collapsed: bool,
+    pub enemies: Vec<UniverseId>,
+    pub brothers: Vec<UniverseId>,
+    pub targeting: TargetingKind,
+    pub world: Option<WorldView>,
+    pub script: Option<PathBuf>,
+}
 
+impl Universe {
+    pub(crate) fn new(id: UniverseId, intent_tx: UnboundedSender<UniverseIntent>, rng: StdRng, targeting: TargetingKind, script: Option<UniverseScript>) -> Universe {
         Universe {
             id,
             intent_tx,
@@ -27,6 +51,32 @@ impl Universe {
             hp: 100,
             enemies: Default::default(),
             brothers: Default::default(),
+            rng,
+            targeting: targeting.strategy(),
+            known_hp: Default::default(),
+            world: None,
+            last_attacker: None,
+            script: script.map(|script| ScriptBrain::new(id, script)),
+        }
+    }
+
+    /// a universe picking up where a snapshot of it left off
+    pub(crate) fn restore(state: &UniverseSnapshot, intent_tx: UnboundedSender<UniverseIntent>, rng: StdRng, script: Option<UniverseScript>) -> Universe {
+        Universe {
+            id: state.id,
+            intent_tx,
+            executes: state.executes,
+            collapsed: false,
+            tick: state.tick,
+            hp: state.hp,
+            enemies: state.enemies.iter().copied().collect(),
+            brothers: state.brothers.iter().copied().collect(),
+            rng,
+            targeting: state.targeting.strategy(),
+            known_hp: Default::default(),
+            world: None,
+            last_attacker: None,
+            script: script.map(|script| ScriptBrain::new(state.id, script)),
         }
     }
 
@@ -34,32 +84,135 @@ impl Universe {
         // death check
         if !self.executes { return; }
 
-        // auto combat every 4 ticks
-        if self.tick % 4 == 0 {
-            self.attack_or_heal_random();
+        // a script decides every tick, the built-in tactics fight every 4 ticks
+        if self.script.is_some() {
+            self.run_script();
+        } else if self.tick % 4 == 0 {
+            self.attack_or_heal();
         }
 
         // tick
         self.tick += 1;
     }
 
-    fn attack_or_heal_random(&mut self) {
-        let mut rng = rng();
-        let strength = rng.random_range(7..=20);
+    fn attack_or_heal(&mut self) {
+        let strength = self.rng.random_range(7..=MAX_STRENGTH);
 
-        if !self.enemies.is_empty() && rng.random_ratio(7, 10) {
-            if let Some(&target) = self.enemies.iter().next() {
-                let _ = self.intent_tx.send(UniverseIntent::Attack {target, damage: strength });
-            }
+        // badly beaten, sue the last attacker for peace now and then
+        if self.hp < 30 && let Some(attacker) = self.last_attacker && self.rng.random_ratio(1, 5) {
+            let _ = self.intent_tx.send(UniverseIntent::ProposePeace { target: attacker });
+            return;
         }
 
-        if !self.brothers.is_empty() && rng.random_ratio(3, 10) {
-            if let Some(&target) = self.brothers.iter().next() {
-                let _ = self.intent_tx.send(UniverseIntent::Heal {target, amount: strength });
+        if !self.enemies.is_empty() && self.rng.random_ratio(7, 10) {
+            let view = TargetingView {
+                enemies: &self.enemies,
+                known_hp: &self.known_hp,
+                last_attacker: self.last_attacker,
+            };
+
+            if let Some(target) = self.targeting.pick(&view, &mut self.rng) {
+                self.attack(target, strength);
             }
         }
+
+        // the brother that needs it most, as far as we can see
+        if !self.brothers.is_empty() && self.rng.random_ratio(3, 10)
+            && let Some(target) = self.brothers.iter().copied().min_by_key(|id| self.known_hp.get(id).copied().unwrap_or(100)) {
+            let _ = self.intent_tx.send(UniverseIntent::Heal {target, amount: strength });
+        }
     }
     
+    fn run_script(&mut self) {
+        let strength = self.rng.random_range(7..=MAX_STRENGTH);
+        let roll = self.rng.random_range(0..100);
+
+        let Some(brain) = &self.script else {
+            return;
+        };
+        let state = ScriptState {
+            id: self.id,
+            hp: self.hp,
+            tick: self.tick,
+            enemies: &self.enemies,
+            brothers: &self.brothers,
+            known_hp: &self.known_hp,
+            last_attacker: self.last_attacker,
+            strength,
+            roll,
+        };
+
+        match brain.decide(&state) {
+            Ok(intents) => {
+                if intents.len() > MAX_SCRIPT_INTENTS {
+                    Log::info(format!(
+                        "script of #{} asked for {} intents, only the first {} count",
+                        self.id, intents.len(), MAX_SCRIPT_INTENTS
+                    ));
+                }
+                for intent in intents.into_iter().take(MAX_SCRIPT_INTENTS) {
+                    if let Err(reason) = self.apply_script_intent(intent) {
+                        Log::info(format!("script of #{}: {}", self.id, reason));
+                    }
+                }
+            }
+            Err(e) => {
+                // a broken script would fail every tick, fall back for good
+                Log::info(format!("script of #{} failed, back to built-in tactics: {}", self.id, e));
+                self.script = None;
+            }
+        }
+    }
+
+    /// scripts can ask for anything, only what the built-in tactics could do goes through
+    fn apply_script_intent(&mut self, intent: ScriptIntent) -> Result<(), String> {
+        let strength_range = 1..=MAX_STRENGTH as rhai::INT;
+
+        match intent {
+            ScriptIntent::Attack { target, damage } => {
+                let target = target as UniverseId;
+                if !self.is_enemy(target) {
+                    return Err(format!("attack on #{} rejected, not an enemy", target));
+                }
+                if !strength_range.contains(&damage) {
+                    return Err(format!("attack of {} rejected, must be 1 to {}", damage, MAX_STRENGTH));
+                }
+                self.attack(target, damage as i32);
+            }
+            ScriptIntent::Heal { target, amount } => {
+                let target = target as UniverseId;
+                if !self.is_brother(target) {
+                    return Err(format!("heal of #{} rejected, not a brother", target));
+                }
+                if !strength_range.contains(&amount) {
+                    return Err(format!("heal of {} rejected, must be 1 to {}", amount, MAX_STRENGTH));
+                }
+                let _ = self.intent_tx.send(UniverseIntent::Heal { target, amount: amount as i32 });
+            }
+            ScriptIntent::Ping { target } => {
+                let target = target as UniverseId;
+                if target == self.id {
+                    return Err("ping of itself rejected".to_owned());
+                }
+                self.ping(target);
+            }
+            ScriptIntent::Diplomacy { target, action } => {
+                let target = target as UniverseId;
+                if target == self.id {
+                    return Err(format!("{} to itself rejected", action));
+                }
+                let _ = self.intent_tx.send(action.intent(target));
+            }
+        }
+        Ok(())
+    }
+
+    fn attack(&mut self, target: UniverseId, damage: i32) {
+        let hp = self.known_hp.entry(target).or_insert(100);
+        *hp = (*hp - damage).max(0);
+        let _ = self.intent_tx.send(UniverseIntent::Attack { target, damage });
+    }
+
     // helper utils for fighting stuff
     pub fn take_damage(&mut self, amount: i32) {
         self.hp -= amount;
@@ -76,6 +229,78 @@ impl Universe {
         let _ = self.intent_tx.send(UniverseIntent::Dead { target: self.id });
     }
 
+    pub fn ping(&self, target: UniverseId) {
+        let _ = self.intent_tx.send(UniverseIntent::Ping { target });
+    }
+
+    pub fn answer_ping(&self, pinger: UniverseId, nonce: u64) {
+        let _ = self.intent_tx.send(UniverseIntent::Pong { target: pinger, nonce });
+    }
+
+    /// a (possibly stale) look at the others, only the ones we relate to matter
+    pub fn perceive(&mut self, view: &WorldView) {
+        let mut world = view.clone();
+        world.neighbours.retain(|id, _| self.is_enemy(*id) || self.is_brother(*id));
+
+        for (id, neighbour) in &world.neighbours {
+            self.known_hp.insert(*id, neighbour.hp);
+        }
+        self.world = Some(world);
+    }
+
+    /// hit by another universe, remembered for revenge
+    pub fn attacked_by(&mut self, attacker: UniverseId, damage: i32) {
+        self.last_attacker = Some(attacker);
+        self.take_damage(damage);
+    }
+
+    /// hostile ones count as enemies, friendly ones as brothers, neutral ones as neither
+    pub fn set_relationship(&mut self, id: UniverseId, relationship: Relationship) {
+        self.enemies.remove(&id);
+        self.brothers.remove(&id);
+
+        if relationship.is_hostile() {
+            self.enemies.insert(id);
+            self.known_hp.entry(id).or_insert(100);
+        } else if relationship.is_friendly() {
+            self.brothers.insert(id);
+        }
+
+        // no revenge on someone we made up with
+        if !relationship.is_hostile() && self.last_attacker == Some(id) {
+            self.last_attacker = None;
+        }
+    }
+
+    /// whether to go along with what another universe proposes
+    pub fn consider(&self, from: UniverseId, action: DiplomaticAction) -> bool {
+        let weak = self.hp < 50;
+        match action {
+            DiplomaticAction::DeclareWar => true,
+            // nobody allies with whoever hit them last, unless desperate
+            DiplomaticAction::Ally => weak || (self.last_attacker != Some(from) && !self.is_enemy(from)),
+            // peace suits whoever isn't winning
+            DiplomaticAction::Peace => weak || self.known_hp.get(&from).is_none_or(|&their_hp| their_hp >= self.hp),
+        }
+    }
+
+    /// the other universe is gone, forget everything about it
+    pub fn forget(&mut self, id: UniverseId) {
+        if self.is_brother(id) {
+            self.brothers.remove(&id);
+        }
+        if self.is_enemy(id) {
+            self.enemies.remove(&id);
+        }
+        self.known_hp.remove(&id);
+        if let Some(world) = &mut self.world {
+            world.neighbours.remove(&id);
+        }
+        if self.last_attacker == Some(id) {
+            self.last_attacker = None;
+        }
+    }
+
     pub fn heal(&mut self, amount: i32) {
         self.hp += amount;
         if self.hp > 100 { self.hp = 100; }
@@ -96,4 +321,30 @@ impl Universe {
     pub fn shutdown(&mut self) {
         self.collapsed = true;
     }
+
+    pub fn status_report(&self) -> StatusReport {
+        let status = if self.collapsed || self.hp <= 0 {
+            UniverseStatus::Collapsing
+        } else if self.executes {
+            UniverseStatus::Running
+        } else {
+            UniverseStatus::Paused
+        };
+        StatusReport { hp: self.hp, status }
+    }
+
+    pub fn snapshot(&self) -> UniverseSnapshot {
+        UniverseSnapshot {
+            id: self.id,
+            hp: self.hp,
+            tick: self.tick,
+            executes: self.executes,
+            collapsed: self.collapsed,
+            enemies: self.enemies.iter().copied().collect(),
+            brothers: self.brothers.iter().copied().collect(),
+            targeting: self.targeting.kind(),
+            world: self.world.clone(),
+            script: self.script.as_ref().map(|brain| brain.script.path.clone()),
+        }
+    }
 }
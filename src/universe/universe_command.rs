@@ -1,12 +1,19 @@
 ﻿use crate::universe::relationship::Relationship;
+use crate::universe::{DiplomaticAction, StatusReport};
 use crate::universe::universe_event::UniverseEvent;
 use crate::universe::UniverseId;
+use crate::universe::UniverseSnapshot;
+use tokio::sync::oneshot;
 
 #[derive(Debug)]
 pub enum UniverseCommand {
+    Tick(oneshot::Sender<StatusReport>), // step once, answers with its hp and status when done
     Start, // Resume
     Stop, // Pause
     InjectEvent(UniverseEvent),
     Shutdown, // Shuts down entirely
     SetRelationship(UniverseId, Relationship),
+    SendPing(UniverseId), // makes the universe ping another universe
+    QueryState(oneshot::Sender<UniverseSnapshot>), // replies with a snapshot of the universe
+    Consider { from: UniverseId, action: DiplomaticAction, reply: oneshot::Sender<bool> }, // accept or reject a proposal
 }
\ No newline at end of file
@@ -1,9 +1,13 @@
 ﻿use crate::universe::id::UniverseId;
+use crate::universe::WorldView;
 
 #[derive(Debug)]
 pub enum UniverseEvent {
     Shatter(i32),                // damage the universe a bit
+    Attacked { by: UniverseId, damage: i32 }, // another universe's attack landed
     Heal(i32),       // heals universe. UniverseId must be "brother" for this to apply.
     Crash,      // the universe is damaged so bad, it crashed. UniverseId must be "Enemy" for this to apply.
     UniverseCollapsed(UniverseId),
+    Ping { from: UniverseId, nonce: u64 }, // must be answered with a pong intent
+    WorldView(WorldView),  // what the supervisor lets this universe see of the others
 }
\ No newline at end of file
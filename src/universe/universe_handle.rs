@@ -1,12 +1,12 @@
-﻿use std::time::Duration;
-use tokio::sync::mpsc::{Sender, unbounded_channel, channel, UnboundedReceiver};
-use tokio::time::{interval, MissedTickBehavior};
+﻿use tokio::sync::mpsc::{Sender, unbounded_channel, channel, UnboundedReceiver};
 use rgb::{Rgb, RGB8};
 use rand::Rng;
+use crate::logging::{FactionRef, UniverseRef};
+use crate::simulation::SimulationSeed;
+use crate::universe::{new_universe_id, UniverseOptions, UniverseScript};
 use crate::universe::intent::UniverseIntent;
 use crate::universe::id::UniverseId;
-use crate::universe::relationship::Relationship;
-use crate::universe::universe::Universe;
+use crate::universe::universe::{Universe, UniverseSnapshot};
 use crate::universe::universe_command::UniverseCommand;
 use crate::universe::universe_event::UniverseEvent;
 
@@ -14,40 +14,26 @@ pub struct UniverseHandle {
     pub(crate) handle_id: UniverseId,
     pub(crate) own_name: String,
     pub(crate) color: RGB8,
+    pub(crate) faction: Option<FactionRef>,
     pub(crate) commander_tx: Sender<UniverseCommand>,
     pub(crate) universe_task_handle: tokio::task::JoinHandle<()>,
-    pub(crate) intent_rx: UnboundedReceiver<UniverseIntent>,
 }
 
 impl UniverseHandle {
-    fn new(mut universe: Universe, intent_rx: UnboundedReceiver<UniverseIntent>, own_name: String, color: Rgb<u8>) -> UniverseHandle {
-        let handle_id = universe.id.clone();
+    fn new(mut universe: Universe, own_name: String, color: Rgb<u8>, faction: Option<FactionRef>) -> UniverseHandle {
+        let handle_id = universe.id;
 
         let (commander_tx, mut command_rx) = channel::<UniverseCommand>(10);
 
+        // no timer of its own, the universe steps when the supervisor's clock ticks
         let universe_task_handle = tokio::spawn(async move{
-            let mut ticker = interval(Duration::from_millis(80)); // 0.4s per step
-            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
-
-            loop {
-                tokio::select! {
-                    // commands from supervisor
-                    Some(command) = command_rx.recv() => {
-                        handle_given_command(&command, &mut universe);
-                    }
-
-                    // other -> nothing is pending, perform step
-                    _ = ticker.tick() => {
-                        // step, while waiting for rx
-                    }
-
-                }
+            // commands from supervisor
+            while let Some(command) = command_rx.recv().await {
+                handle_given_command(command, &mut universe);
 
                 if universe.is_collapsed() {
                     return
                 }
-
-                (&mut universe).step();
             }
         });
 
@@ -55,29 +41,57 @@ impl UniverseHandle {
             handle_id,
             own_name,
             color,
+            faction,
             commander_tx,
             universe_task_handle,
-            intent_rx,
+        }
+    }
+
+    /// how this universe is referred to in log events
+    pub fn universe_ref(&self) -> UniverseRef {
+        UniverseRef {
+            id: self.handle_id,
+            name: self.own_name.clone(),
+            color: self.color,
+            faction: self.faction.clone(),
         }
     }
 }
 
-pub fn create_universe_handle(name: String) -> UniverseHandle {
+/// spawns a new universe task. the intent receiver is handed out separately,
+/// the supervisor listens on it while the handle is used to command the universe.
+pub fn create_universe_handle(name: String, options: &UniverseOptions, script: Option<UniverseScript>, faction: Option<FactionRef>, seed: &SimulationSeed) -> (UniverseHandle, UnboundedReceiver<UniverseIntent>) {
     let (intent_tx, intent_rx) = unbounded_channel::<UniverseIntent>();
 
+    let id = new_universe_id();
+    let mut rng = seed.universe_rng(id);
+
     let color = RGB8::new(
-        rand::rng().random_range(50..255),
-        rand::rng().random_range(50..255),
-        rand::rng().random_range(50..255),
+        rng.random_range(50..255),
+        rng.random_range(50..255),
+        rng.random_range(50..255),
     );
 
-    let universe = Universe::new(intent_tx);
+    let universe = Universe::new(id, intent_tx, rng, options.targeting, script);
+
+    (UniverseHandle::new(universe, name, color, faction), intent_rx)
+}
+
+/// re-spawns a saved universe, keeping its old id, colour and state
+pub fn restore_universe_handle(name: String, color: RGB8, state: &UniverseSnapshot, script: Option<UniverseScript>, faction: Option<FactionRef>, seed: &SimulationSeed) -> (UniverseHandle, UnboundedReceiver<UniverseIntent>) {
+    let (intent_tx, intent_rx) = unbounded_channel::<UniverseIntent>();
+
+    let universe = Universe::restore(state, intent_tx, seed.universe_rng(state.id), script);
 
-    UniverseHandle::new(universe, intent_rx, name, color)
+    (UniverseHandle::new(universe, name, color, faction), intent_rx)
 }
 
-fn handle_given_command(command: &UniverseCommand, universe: &mut Universe) {
+fn handle_given_command(command: UniverseCommand, universe: &mut Universe) {
     match command {
+        UniverseCommand::Tick(done_tx) => {
+            universe.step();
+            let _ = done_tx.send(universe.status_report());
+        }
         UniverseCommand::Start => {
             universe.executes = true;
         }
@@ -85,19 +99,24 @@ fn handle_given_command(command: &UniverseCommand, universe: &mut Universe) {
             universe.executes = false;
         }
         UniverseCommand::InjectEvent(event) => {
-            handle_given_event(event, universe);
+            handle_given_event(&event, universe);
         }
         UniverseCommand::SetRelationship(id, relationship) => {
-            match relationship {
-                Relationship::Enemy => { universe.enemies.insert(id.clone()); }
-                Relationship::Brother => { universe.brothers.insert(id.clone()); }
-            }
+            universe.set_relationship(id, relationship);
+        }
+        UniverseCommand::SendPing(target) => {
+            universe.ping(target);
+        }
+        UniverseCommand::QueryState(reply_tx) => {
+            // the asker may have given up waiting, nothing to do then
+            let _ = reply_tx.send(universe.snapshot());
+        }
+        UniverseCommand::Consider { from, action, reply } => {
+            let _ = reply.send(universe.consider(from, action));
         }
         UniverseCommand::Shutdown => {
             universe.executes = false;
             universe.shutdown();
-            
-            return;
         }
     }
 }
@@ -107,6 +126,9 @@ fn handle_given_event(event: &UniverseEvent, universe: &mut Universe) {
         UniverseEvent::Shatter(strength) => {
             universe.take_damage(*strength);
         }
+        UniverseEvent::Attacked { by, damage } => {
+            universe.attacked_by(*by, *damage);
+        }
         UniverseEvent::Crash => {
             universe.take_damage(999);  // insta-kill
         }
@@ -114,12 +136,13 @@ fn handle_given_event(event: &UniverseEvent, universe: &mut Universe) {
             universe.heal(*strength);
         }
         UniverseEvent::UniverseCollapsed(collapsed_id) => {
-            if universe.is_brother(*collapsed_id) {
-                universe.brothers.remove(&collapsed_id);
-            }
-            if universe.is_enemy(*collapsed_id) {
-                universe.enemies.remove(&collapsed_id);
-            }
+            universe.forget(*collapsed_id);
+        }
+        UniverseEvent::Ping { from, nonce } => {
+            universe.answer_ping(*from, *nonce);
+        }
+        UniverseEvent::WorldView(view) => {
+            universe.perceive(view);
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,22 @@
+﻿use std::collections::BTreeMap;
+use crate::universe::UniverseId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighbourStatus {
+    Running,
+    Paused,
+}
+
+/// how another universe looked when the supervisor last observed it
+#[derive(Debug, Clone)]
+pub struct NeighbourView {
+    pub hp: i32,
+    pub status: NeighbourStatus,
+}
+
+/// what a universe perceives of the others, as of `tick`. may be old news, see the fog setting.
+#[derive(Debug, Clone)]
+pub struct WorldView {
+    pub tick: u64,
+    pub neighbours: BTreeMap<UniverseId, NeighbourView>,
+}